@@ -3,7 +3,7 @@ use std::str::FromStr;
 use tracing::{error, instrument};
 use tree_sitter::Node;
 
-use crate::lsp::docs::ValueRequirment;
+use crate::lsp::docs::{Script, ValueRequirment};
 
 #[derive(Clone, Debug, Default)]
 pub struct TrunkAttrState {
@@ -24,6 +24,35 @@ impl TrunkAttrState {
         }
     }
 
+    /// The attributes accepted by the current asset.
+    ///
+    /// Script elements don't have a `rel` attribute so they are handled through the tag name.
+    pub fn asset_attrs(&self) -> Option<&'static [(&'static str, &'static str, ValueRequirment)]> {
+        match self.tag_name {
+            TagName::Script => Some(Script::ASSET_ATTRS),
+            TagName::Link => self.rel.map(AssetType::to_info),
+            TagName::Unknown => None,
+        }
+    }
+
+    /// The attributes the current asset must have.
+    pub fn required_attrs(&self) -> &'static [(&'static str, &'static str, ValueRequirment)] {
+        match self.tag_name {
+            TagName::Script => Script::REQUIRED_ASSET_ATTRS,
+            TagName::Link => self.rel.map(AssetType::to_required).unwrap_or_default(),
+            TagName::Unknown => &[],
+        }
+    }
+
+    /// The default values of the attributes accepted by the current asset.
+    pub fn asset_defaults(&self) -> &'static [(&'static str, &'static str)] {
+        match self.tag_name {
+            TagName::Script => Script::ASSET_DEFAULTS,
+            TagName::Link => self.rel.map(AssetType::to_defaults).unwrap_or_default(),
+            TagName::Unknown => &[],
+        }
+    }
+
     pub fn is_rel_val(&self, s: &str, n: Node) -> bool {
         if self.rel.is_some() {
             return false;
@@ -88,6 +117,94 @@ impl TrunkAttrState {
     }
 }
 
+/// An attribute of an element along with the nodes it was created from.
+#[derive(Clone, Copy, Debug)]
+pub struct Attr<'a> {
+    pub name: &'a str,
+    /// The unquoted value of the attribute.
+    ///
+    /// `None` if the attribute has no value, for example `data-no-minify`.
+    pub value: Option<&'a str>,
+    /// The node with a kind of "attribute".
+    pub node: Node<'a>,
+    /// The node with a kind of "quoted_attribute_value" or "attribute_value".
+    pub value_node: Option<Node<'a>>,
+}
+
+impl<'a> Attr<'a> {
+    /// Accepts a node with a kind of "attribute".
+    pub fn from_node(s: &'a str, node: Node<'a>) -> Option<Self> {
+        let name_node = node
+            .named_child(0)
+            .filter(|c| c.kind() == "attribute_name")?;
+        let name = name_node.utf8_text(s.as_bytes()).ok()?;
+        let value_node = node
+            .named_child(1)
+            .filter(|c| matches!(c.kind(), "attribute_value" | "quoted_attribute_value"));
+        let value = match value_node {
+            Some(v) if v.kind() == "quoted_attribute_value" => Some(
+                v.named_child(0)
+                    .and_then(|c| c.utf8_text(s.as_bytes()).ok())
+                    .unwrap_or_default(),
+            ),
+            Some(v) => v.utf8_text(s.as_bytes()).ok(),
+            None => None,
+        };
+
+        Some(Self {
+            name,
+            value,
+            node,
+            value_node,
+        })
+    }
+
+    /// The node of the value without its quotes.
+    ///
+    /// `None` if the attribute has no value or the quotes are empty.
+    pub fn content_node(&self) -> Option<Node<'a>> {
+        self.value_node.and_then(|v| match v.kind() {
+            "quoted_attribute_value" => v.named_child(0),
+            _ => Some(v),
+        })
+    }
+}
+
+/// The attributes of an element in the order they are written.
+#[derive(Clone, Debug, Default)]
+pub struct ElemAttrs<'a> {
+    pub attrs: Vec<Attr<'a>>,
+}
+
+impl<'a> ElemAttrs<'a> {
+    /// Accepts a node with a kind of "start_tag" or "self_closing_tag".
+    pub fn from_elem(s: &'a str, elem: Node<'a>) -> Self {
+        let mut cursor = elem.walk();
+        let attrs = elem
+            .named_children(&mut cursor)
+            .filter(|n| n.kind() == "attribute")
+            .filter_map(|n| Attr::from_node(s, n))
+            .collect();
+
+        Self { attrs }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Attr<'a>> {
+        self.attrs.iter().find(|attr| attr.name == name)
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Returns the value of an attribute if it is present and not empty.
+    pub fn value(&self, name: &str) -> Option<&'a str> {
+        self.get(name)
+            .and_then(|attr| attr.value)
+            .filter(|val| !val.is_empty())
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 pub enum TagName {
     #[default]
@@ -120,6 +237,34 @@ pub enum AssetType {
 }
 
 impl AssetType {
+    /// The `rel` values of all asset types.
+    pub const NAMES: &'static [&'static str] = &[
+        "rust",
+        "css",
+        "tailwind-css",
+        "sass",
+        "scss",
+        "icon",
+        "inline",
+        "copy-file",
+        "copy-dir",
+    ];
+
+    /// The `rel` value of the asset type.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AssetType::Rust => "rust",
+            AssetType::Css => "css",
+            AssetType::Tailwind => "tailwind-css",
+            AssetType::Sass => "sass",
+            AssetType::Scss => "scss",
+            AssetType::Icon => "icon",
+            AssetType::Inline => "inline",
+            AssetType::CopyFile => "copy-file",
+            AssetType::CopyDir => "copy-dir",
+        }
+    }
+
     pub fn to_info(self) -> &'static [(&'static str, &'static str, ValueRequirment)] {
         use crate::lsp::docs::*;
         match self {
@@ -134,6 +279,52 @@ impl AssetType {
             AssetType::Inline => RelInline::ASSET_ATTRS,
         }
     }
+
+    pub fn to_required(self) -> &'static [(&'static str, &'static str, ValueRequirment)] {
+        use crate::lsp::docs::*;
+        match self {
+            AssetType::Rust => RelRust::REQUIRED_ASSET_ATTRS,
+            AssetType::Css => RelCss::REQUIRED_ASSET_ATTRS,
+            AssetType::Sass => RelSass::REQUIRED_ASSET_ATTRS,
+            AssetType::Scss => RelScss::REQUIRED_ASSET_ATTRS,
+            AssetType::Icon => RelIcon::REQUIRED_ASSET_ATTRS,
+            AssetType::Tailwind => RelTailwind::REQUIRED_ASSET_ATTRS,
+            AssetType::CopyDir => RelCopyDir::REQUIRED_ASSET_ATTRS,
+            AssetType::CopyFile => RelCopyFile::REQUIRED_ASSET_ATTRS,
+            AssetType::Inline => RelInline::REQUIRED_ASSET_ATTRS,
+        }
+    }
+
+    /// The documentation of the asset type.
+    pub fn to_doc(self) -> &'static str {
+        use crate::lsp::docs::*;
+        match self {
+            AssetType::Rust => RelRust::as_str(),
+            AssetType::Css => RelCss::as_str(),
+            AssetType::Sass => RelSass::as_str(),
+            AssetType::Scss => RelScss::as_str(),
+            AssetType::Icon => RelIcon::as_str(),
+            AssetType::Tailwind => RelTailwind::as_str(),
+            AssetType::CopyDir => RelCopyDir::as_str(),
+            AssetType::CopyFile => RelCopyFile::as_str(),
+            AssetType::Inline => RelInline::as_str(),
+        }
+    }
+
+    pub fn to_defaults(self) -> &'static [(&'static str, &'static str)] {
+        use crate::lsp::docs::*;
+        match self {
+            AssetType::Rust => RelRust::ASSET_DEFAULTS,
+            AssetType::Css => RelCss::ASSET_DEFAULTS,
+            AssetType::Sass => RelSass::ASSET_DEFAULTS,
+            AssetType::Scss => RelScss::ASSET_DEFAULTS,
+            AssetType::Icon => RelIcon::ASSET_DEFAULTS,
+            AssetType::Tailwind => RelTailwind::ASSET_DEFAULTS,
+            AssetType::CopyDir => RelCopyDir::ASSET_DEFAULTS,
+            AssetType::CopyFile => RelCopyFile::ASSET_DEFAULTS,
+            AssetType::Inline => RelInline::ASSET_DEFAULTS,
+        }
+    }
 }
 
 impl FromStr for AssetType {
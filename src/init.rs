@@ -1,13 +1,97 @@
 use lsp_types::{
-    CompletionOptions, HoverProviderCapability, InitializeParams, InitializeResult,
-    PositionEncodingKind, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
-    TextDocumentSyncKind,
+    CodeActionProviderCapability, CodeLensOptions, CompletionOptions, ExecuteCommandOptions,
+    FileOperationFilter, FileOperationPattern, FileOperationRegistrationOptions,
+    HoverProviderCapability, InitializeParams, InitializeResult, MarkupKind, OneOf,
+    PositionEncodingKind, RenameOptions, ResourceOperationKind, SemanticTokensFullOptions,
+    SemanticTokensOptions, SemanticTokensServerCapabilities, ServerCapabilities, ServerInfo,
+    TextDocumentSyncCapability, TextDocumentSyncKind, WorkspaceFileOperationsServerCapabilities,
+    WorkspaceServerCapabilities,
 };
 
+use std::{path::PathBuf, sync::OnceLock};
+
 use texter::core::text::Text;
 
+use crate::{
+    lsp::{code_lens::OPEN_COMMAND, preview::PREVIEW_COMMAND, semantic_tokens},
+    utils::uri_to_path,
+};
+
 pub type TextFn = fn(String) -> Text;
 
+/// The features of the client that change the content of responses.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClientCaps {
+    /// Whether completion items may use snippet syntax.
+    pub snippets: bool,
+    /// Whether the documentation of completion items is rendered as Markdown.
+    pub completion_markdown: bool,
+    /// Whether hover contents are rendered as Markdown.
+    pub hover_markdown: bool,
+    /// Whether completion items may distinguish between inserting and replacing text.
+    pub insert_replace: bool,
+    /// Whether workspace edits may create files.
+    pub create_files: bool,
+    /// Whether the client can be asked to show a document with `window/showDocument`.
+    pub show_document: bool,
+}
+
+static CLIENT_CAPS: OnceLock<ClientCaps> = OnceLock::new();
+
+impl ClientCaps {
+    /// Stores the capabilities the client sent in the initialize request.
+    pub fn init(p: &InitializeParams) {
+        let text_document = p.capabilities.text_document.as_ref();
+        let completion_item = text_document
+            .and_then(|td| td.completion.as_ref())
+            .and_then(|c| c.completion_item.as_ref());
+        let workspace_edit = p
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|w| w.workspace_edit.as_ref());
+        let supports_markdown = |formats: Option<&Vec<MarkupKind>>| {
+            formats.is_some_and(|f| f.contains(&MarkupKind::Markdown))
+        };
+
+        let caps = ClientCaps {
+            snippets: completion_item
+                .and_then(|ci| ci.snippet_support)
+                .unwrap_or(false),
+            completion_markdown: supports_markdown(
+                completion_item.and_then(|ci| ci.documentation_format.as_ref()),
+            ),
+            hover_markdown: supports_markdown(
+                text_document
+                    .and_then(|td| td.hover.as_ref())
+                    .and_then(|h| h.content_format.as_ref()),
+            ),
+            insert_replace: completion_item
+                .and_then(|ci| ci.insert_replace_support)
+                .unwrap_or(false),
+            create_files: workspace_edit.is_some_and(|we| {
+                we.document_changes == Some(true)
+                    && we
+                        .resource_operations
+                        .as_ref()
+                        .is_some_and(|ops| ops.contains(&ResourceOperationKind::Create))
+            }),
+            show_document: p
+                .capabilities
+                .window
+                .as_ref()
+                .and_then(|w| w.show_document.as_ref())
+                .is_some_and(|sd| sd.support),
+        };
+        let _ = CLIENT_CAPS.set(caps);
+    }
+
+    /// The capabilities of the client, nothing is assumed to be supported before initialization.
+    pub fn get() -> ClientCaps {
+        CLIENT_CAPS.get().copied().unwrap_or_default()
+    }
+}
+
 pub fn initialize_result(p: &InitializeParams) -> (TextFn, InitializeResult) {
     let pos_encoding = p
         .capabilities
@@ -28,6 +112,49 @@ pub fn initialize_result(p: &InitializeParams) -> (TextFn, InitializeResult) {
                 trigger_characters: Some(vec!["-".to_string(), "\"".to_string(), " ".to_string()]),
                 ..Default::default()
             }),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            inlay_hint_provider: Some(OneOf::Left(true)),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            references_provider: Some(OneOf::Left(true)),
+            document_highlight_provider: Some(OneOf::Left(true)),
+            code_lens_provider: Some(CodeLensOptions {
+                resolve_provider: Some(true),
+            }),
+            semantic_tokens_provider: Some(
+                SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+                    legend: semantic_tokens::legend(),
+                    range: Some(true),
+                    full: Some(SemanticTokensFullOptions::Bool(true)),
+                    ..Default::default()
+                }),
+            ),
+            rename_provider: Some(OneOf::Right(RenameOptions {
+                prepare_provider: Some(true),
+                work_done_progress_options: Default::default(),
+            })),
+            workspace: Some(WorkspaceServerCapabilities {
+                workspace_folders: None,
+                file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                    will_rename: Some(FileOperationRegistrationOptions {
+                        filters: vec![FileOperationFilter {
+                            scheme: Some("file".to_string()),
+                            pattern: FileOperationPattern {
+                                glob: "**/*".to_string(),
+                                matches: None,
+                                options: None,
+                            },
+                        }],
+                    }),
+                    ..Default::default()
+                }),
+            }),
+            workspace_symbol_provider: Some(OneOf::Left(true)),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            document_range_formatting_provider: Some(OneOf::Left(true)),
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: vec![PREVIEW_COMMAND.to_string(), OPEN_COMMAND.to_string()],
+                ..Default::default()
+            }),
             ..Default::default()
         },
         server_info: Some(ServerInfo {
@@ -38,6 +165,35 @@ pub fn initialize_result(p: &InitializeParams) -> (TextFn, InitializeResult) {
     (t_fn, res)
 }
 
+/// The directories of the workspace folders, or the root of the workspace for clients that do
+/// not support multiple folders.
+#[allow(deprecated)]
+pub fn workspace_roots(p: &InitializeParams) -> Vec<PathBuf> {
+    match &p.workspace_folders {
+        Some(folders) => folders
+            .iter()
+            .filter_map(|folder| uri_to_path(&folder.uri))
+            .collect(),
+        None => p
+            .root_uri
+            .as_ref()
+            .and_then(uri_to_path)
+            .or_else(|| p.root_path.as_ref().map(PathBuf::from))
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Whether the client can notify the server about changed files after registering a watcher.
+pub fn supports_file_watching(p: &InitializeParams) -> bool {
+    p.capabilities
+        .workspace
+        .as_ref()
+        .and_then(|w| w.did_change_watched_files.as_ref())
+        .and_then(|w| w.dynamic_registration)
+        .unwrap_or(false)
+}
+
 fn decide_encoding(encs: Option<&[PositionEncodingKind]>) -> (TextFn, PositionEncodingKind) {
     const DEFAULT: (TextFn, PositionEncodingKind) = (Text::new_utf16, PositionEncodingKind::UTF16);
     let Some(encs) = encs else {
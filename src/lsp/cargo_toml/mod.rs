@@ -0,0 +1,66 @@
+use std::ops::Range;
+
+use toml_edit::{ImDocument, Value};
+
+/// Returns the byte ranges of the strings naming a binary, without their quotes.
+///
+/// These are the `name` of the `[[bin]]` table and `package.default-run` if it refers to the
+/// binary. Returns `None` if the manifest cannot be parsed or has no `[[bin]]` with the name.
+pub fn bin_name_ranges(s: &str, name: &str) -> Option<Vec<Range<usize>>> {
+    let doc = ImDocument::parse(s).ok()?;
+    let bin_name = doc
+        .get("bin")
+        .and_then(|bin| bin.as_array_of_tables())?
+        .iter()
+        .filter_map(|bin| bin.get("name").and_then(|name| name.as_value()))
+        .find(|value| value.as_str() == Some(name))?;
+
+    let default_run = doc
+        .get("package")
+        .and_then(|package| package.get("default-run"))
+        .and_then(|default_run| default_run.as_value())
+        .filter(|value| value.as_str() == Some(name));
+
+    Some(
+        [Some(bin_name), default_run]
+            .into_iter()
+            .flatten()
+            .filter_map(|value| string_content(s, value))
+            .collect(),
+    )
+}
+
+/// The byte range of a single line string value between its quotes.
+fn string_content(s: &str, value: &Value) -> Option<Range<usize>> {
+    let span = value.span()?;
+    let content = span.start + 1..span.end - 1;
+    // Strings with escapes or multiple lines are not renamed.
+    (s.get(content.clone())? == value.as_str()?).then_some(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bin_name_ranges;
+
+    #[test]
+    fn bin_names() {
+        let s = r#"
+[package]
+name = "app"
+default-run = "worker"
+
+[[bin]]
+name = "app"
+path = "src/main.rs"
+
+[[bin]]
+name = 'worker'
+path = "src/worker.rs"
+"#;
+        let ranges = bin_name_ranges(s, "worker").unwrap();
+        assert_eq!(ranges.len(), 2);
+        assert!(ranges.iter().all(|range| &s[range.clone()] == "worker"));
+        assert_eq!(bin_name_ranges(s, "app").unwrap().len(), 1);
+        assert!(bin_name_ranges(s, "missing").is_none());
+    }
+}
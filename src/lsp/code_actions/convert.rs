@@ -0,0 +1,92 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{CodeAction, CodeActionKind, Range, TextEdit, Uri, WorkspaceEdit};
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::{ElemAttrs, TagName},
+    lsp::queries::attributes::link_script_elements,
+    utils::{is_local, node_range, point_range},
+};
+use texter::core::text::Text;
+
+use super::intersects;
+
+/// Offers to convert plain stylesheet links and scripts into Trunk assets.
+pub fn convert_actions(uri: &Uri, range: Range, n: Node, text: &Text) -> Vec<CodeAction> {
+    let s = text.text.as_str();
+    let mut actions = vec![];
+    for elem in link_script_elements(n, s) {
+        let Some(elem_range) = node_range(text, elem) else {
+            continue;
+        };
+        if !intersects(elem_range, range) {
+            continue;
+        }
+        let Some(tag_name) = elem.named_child(0).filter(|n| n.kind() == "tag_name") else {
+            continue;
+        };
+        let attrs = ElemAttrs::from_elem(s, elem);
+        if attrs.has("data-trunk") {
+            continue;
+        }
+
+        let tag = TagName::from(tag_name.utf8_text(s.as_bytes()).unwrap_or_default());
+        let end = tag_name.end_position();
+        let Some(insert_range) = point_range(text, end, end) else {
+            continue;
+        };
+        let mut edits = vec![TextEdit {
+            range: insert_range,
+            new_text: " data-trunk".to_string(),
+        }];
+
+        let title = match tag {
+            TagName::Link => {
+                let Some(rel) = attrs.get("rel").filter(|rel| {
+                    rel.value
+                        .is_some_and(|val| val.eq_ignore_ascii_case("stylesheet"))
+                }) else {
+                    continue;
+                };
+                let Some(href) = attrs.value("href").filter(|href| is_local(href)) else {
+                    continue;
+                };
+                let Some(rel_range) = rel.content_node().and_then(|val| node_range(text, val))
+                else {
+                    continue;
+                };
+
+                let asset = match Path::new(href).extension().and_then(|ext| ext.to_str()) {
+                    Some("sass") => "sass",
+                    Some("scss") => "scss",
+                    _ => "css",
+                };
+                edits.push(TextEdit {
+                    range: rel_range,
+                    new_text: asset.to_string(),
+                });
+                format!("Convert to a Trunk `{asset}` asset")
+            }
+            TagName::Script => {
+                if !attrs.value("src").is_some_and(is_local) {
+                    continue;
+                }
+                "Convert to a Trunk script asset".to_string()
+            }
+            TagName::Unknown => continue,
+        };
+
+        actions.push(CodeAction {
+            title,
+            kind: Some(CodeActionKind::REFACTOR_REWRITE),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(uri.clone(), edits)])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+    }
+
+    actions
+}
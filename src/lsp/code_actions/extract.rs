@@ -0,0 +1,182 @@
+use std::{path::Path, str::FromStr};
+
+use lsp_types::{
+    CodeAction, CodeActionKind, CreateFile, CreateFileOptions, DocumentChangeOperation,
+    DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier, Position, Range, ResourceOp,
+    TextDocumentEdit, TextEdit, Uri, WorkspaceEdit,
+};
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::ElemAttrs,
+    init::ClientCaps,
+    lsp::queries::attributes::style_script_elements,
+    utils::{node_range, uri_to_path},
+};
+use texter::core::text::Text;
+
+use super::intersects;
+
+/// Offers to move the content of inline style and script elements into a new file that is
+/// included as a Trunk asset.
+///
+/// The new file is created through the workspace edit, so nothing is offered to clients that
+/// cannot create files.
+pub fn extract_actions(uri: &Uri, range: Range, n: Node, text: &Text) -> Vec<CodeAction> {
+    if !ClientCaps::get().create_files {
+        return vec![];
+    }
+    let s = text.text.as_str();
+    let dir = uri_to_path(uri).and_then(|html| Some(html.parent()?.to_path_buf()));
+    let mut actions = vec![];
+    for elem in style_script_elements(n, s) {
+        let Some(elem_range) = node_range(text, elem) else {
+            continue;
+        };
+        if !intersects(elem_range, range) {
+            continue;
+        }
+        let Some(start_tag) = elem.named_child(0).filter(|n| n.kind() == "start_tag") else {
+            continue;
+        };
+        let Some(raw_text) = elem
+            .named_child(1)
+            .filter(|n| n.kind() == "raw_text")
+            .and_then(|n| n.utf8_text(s.as_bytes()).ok())
+        else {
+            continue;
+        };
+        let Some(content) = dedent(raw_text) else {
+            continue;
+        };
+
+        let attrs = ElemAttrs::from_elem(s, start_tag);
+        if attrs.has("data-trunk") || attrs.has("src") {
+            continue;
+        }
+        // Scripts such as `type="importmap"` or templates are not JavaScript.
+        if elem.kind() == "script_element" && !attrs.value("type").is_none_or(is_js_type) {
+            continue;
+        }
+
+        let (stem, ext) = match elem.kind() {
+            "style_element" => ("style", "css"),
+            _ => ("script", "js"),
+        };
+        let name = available_name(dir.as_deref(), stem, ext);
+        // Attributes such as `type="module"` or `media` are kept on the new element.
+        let rest = attrs
+            .attrs
+            .iter()
+            .filter_map(|attr| attr.node.utf8_text(s.as_bytes()).ok())
+            .fold(String::new(), |acc, attr| acc + " " + attr);
+        let replacement = match elem.kind() {
+            "style_element" => format!(r#"<link data-trunk rel="css" href="{name}"{rest}>"#),
+            _ => format!(r#"<script data-trunk src="{name}"{rest}></script>"#),
+        };
+
+        let Some(new_uri) = sibling_uri(uri, &name) else {
+            continue;
+        };
+        let operations = vec![
+            DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                uri: new_uri.clone(),
+                options: Some(CreateFileOptions {
+                    overwrite: Some(false),
+                    ignore_if_exists: Some(false),
+                }),
+                annotation_id: None,
+            })),
+            DocumentChangeOperation::Edit(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri: new_uri,
+                    version: None,
+                },
+                edits: vec![OneOf::Left(TextEdit {
+                    range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+                    new_text: content,
+                })],
+            }),
+            DocumentChangeOperation::Edit(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri: uri.clone(),
+                    version: None,
+                },
+                edits: vec![OneOf::Left(TextEdit {
+                    range: elem_range,
+                    new_text: replacement,
+                })],
+            }),
+        ];
+
+        actions.push(CodeAction {
+            title: format!("Extract into `{name}`"),
+            kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+            edit: Some(WorkspaceEdit {
+                document_changes: Some(DocumentChanges::Operations(operations)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+    }
+
+    actions
+}
+
+/// Whether the `type` of a script element refers to JavaScript.
+fn is_js_type(ty: &str) -> bool {
+    let ty = ty.trim().to_ascii_lowercase();
+    ty == "module"
+        || matches!(
+            ty.strip_prefix("text/")
+                .or_else(|| ty.strip_prefix("application/")),
+            Some("javascript" | "ecmascript" | "x-javascript" | "x-ecmascript" | "jscript")
+        )
+}
+
+/// Removes the blank lines around the content and the indentation shared by all lines.
+///
+/// Returns `None` if there is no content.
+fn dedent(raw_text: &str) -> Option<String> {
+    let lines: Vec<&str> = raw_text.lines().collect();
+    let first = lines.iter().position(|l| !l.trim().is_empty())?;
+    let last = lines.iter().rposition(|l| !l.trim().is_empty())?;
+    let lines = &lines[first..=last];
+
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut content = lines
+        .iter()
+        .map(|l| l.get(indent..).unwrap_or(l.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    content.push('\n');
+
+    Some(content)
+}
+
+/// Finds a file name next to the HTML file that is not taken yet, e.g. `style.css` or
+/// `style-1.css`.
+fn available_name(dir: Option<&Path>, stem: &str, ext: &str) -> String {
+    let Some(dir) = dir else {
+        return format!("{stem}.{ext}");
+    };
+
+    (0..)
+        .map(|i| match i {
+            0 => format!("{stem}.{ext}"),
+            i => format!("{stem}-{i}.{ext}"),
+        })
+        .find(|name| !dir.join(name).exists())
+        .unwrap()
+}
+
+/// The uri of a file in the same directory as the document.
+fn sibling_uri(uri: &Uri, name: &str) -> Option<Uri> {
+    let (dir, _) = uri.as_str().rsplit_once('/')?;
+    Uri::from_str(&format!("{dir}/{name}")).ok()
+}
@@ -0,0 +1,56 @@
+mod convert;
+mod extract;
+
+use std::collections::HashMap;
+
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Position, Range, Uri, WorkspaceEdit,
+};
+use tree_sitter::Node;
+
+use texter::core::text::Text;
+
+use super::diagnostics::problems;
+use convert::convert_actions;
+use extract::extract_actions;
+
+/// Returns the code actions available for the provided range.
+pub fn code_actions(uri: &Uri, range: Range, n: Node, text: &Text) -> Vec<CodeActionOrCommand> {
+    let mut actions = vec![];
+    for problem in problems(uri, n, text) {
+        if !intersects(problem.diagnostic.range, range) {
+            continue;
+        }
+
+        for fix in problem.fixes {
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: fix.title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![problem.diagnostic.clone()]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), fix.edits)])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }));
+        }
+    }
+
+    actions.extend(
+        convert_actions(uri, range, n, text)
+            .into_iter()
+            .map(CodeActionOrCommand::CodeAction),
+    );
+    actions.extend(
+        extract_actions(uri, range, n, text)
+            .into_iter()
+            .map(CodeActionOrCommand::CodeAction),
+    );
+
+    actions
+}
+
+fn intersects(a: Range, b: Range) -> bool {
+    let key = |p: Position| (p.line, p.character);
+    key(a.start) <= key(b.end) && key(b.start) <= key(a.end)
+}
@@ -0,0 +1,208 @@
+use std::{
+    path::Path,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use lsp_server::{Connection, Message, Request, RequestId};
+use lsp_types::{
+    request::{Request as _, ShowDocument},
+    CodeLens, Command, ShowDocumentParams, Uri,
+};
+use serde_json::{json, Value};
+use toml_edit::DocumentMut;
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::{AssetType, ElemAttrs, TrunkAttrState},
+    init::ClientCaps,
+    utils::{node_range, path_to_uri, relative_path, uri_to_path},
+};
+use texter::core::text::Text;
+
+use super::queries::attributes::trunk_elements;
+
+/// Opens the file passed as the first argument in the client, if it supports showing documents.
+pub const OPEN_COMMAND: &str = "trunkls.openFile";
+
+/// Returns the unresolved code lenses of all Trunk elements.
+///
+/// Rust assets get a lens with information about their crate and every asset with a file as its
+/// source gets a lens opening it. The lenses are completed by [`resolve_code_lens`].
+///
+/// Assets whose source file does not exist get no lenses. Lenses that open a file are only
+/// returned if the client can show documents.
+pub fn code_lenses(uri: &Uri, n: Node, text: &Text) -> Vec<CodeLens> {
+    let show_document = ClientCaps::get().show_document;
+    let Some(dir) = uri_to_path(uri).and_then(|html| Some(html.parent()?.to_path_buf())) else {
+        return vec![];
+    };
+    let s = text.text.as_str();
+    let mut lenses = vec![];
+    for elem in trunk_elements(n, s) {
+        let mut cursor = elem.walk();
+        let Some(attr_state) = TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))
+        else {
+            continue;
+        };
+        let attrs = ElemAttrs::from_elem(s, elem);
+        let Some(path) = attr_state
+            .source_path(&dir, &attrs)
+            .filter(|path| path.is_file())
+        else {
+            continue;
+        };
+        // Paths are passed as URIs, which are always valid JSON strings.
+        let Some(source_uri) = path_to_uri(&path) else {
+            continue;
+        };
+        let Some(range) = node_range(text, elem) else {
+            continue;
+        };
+
+        if attr_state.rel == Some(AssetType::Rust) {
+            lenses.push(CodeLens {
+                range,
+                command: None,
+                data: Some(json!({
+                    "kind": "crate",
+                    "uri": source_uri,
+                    "features": attrs.value("data-cargo-features"),
+                    "noDefaultFeatures": attrs.has("data-cargo-no-default-features"),
+                    "allFeatures": attrs.has("data-cargo-all-features"),
+                })),
+            });
+        }
+        // Directories cannot be opened as a document.
+        if show_document && attr_state.rel != Some(AssetType::CopyDir) {
+            lenses.push(CodeLens {
+                range,
+                command: None,
+                data: Some(json!({
+                    "kind": "open",
+                    "uri": source_uri,
+                    "name": relative_path(&dir, &path),
+                })),
+            });
+        }
+    }
+
+    lenses
+}
+
+/// Adds the command to a lens returned by [`code_lenses`].
+///
+/// The crate information has no action if the client cannot show documents.
+pub fn resolve_code_lens(mut lens: CodeLens) -> CodeLens {
+    let Some(data) = lens.data.as_ref() else {
+        return lens;
+    };
+    let Some(uri) = data
+        .get("uri")
+        .and_then(|uri| serde_json::from_value::<Uri>(uri.clone()).ok())
+    else {
+        return lens;
+    };
+    let Some(path) = uri_to_path(&uri) else {
+        return lens;
+    };
+
+    let title = match data["kind"].as_str() {
+        Some("crate") => crate_info(&path, data),
+        Some("open") => format!("Open {}", data["name"].as_str().unwrap_or_default()),
+        _ => return lens,
+    };
+    // Clicking the crate information opens the Cargo.toml.
+    let command = match ClientCaps::get().show_document {
+        true => Command {
+            title,
+            command: OPEN_COMMAND.to_string(),
+            arguments: Some(vec![json!(uri)]),
+        },
+        // Lenses with an empty command are shown as a plain label.
+        false => Command {
+            title,
+            command: String::new(),
+            arguments: None,
+        },
+    };
+    lens.command = Some(command);
+
+    lens
+}
+
+/// Asks the client to show the document.
+pub fn open_file(con: &Connection, uri: Uri) -> anyhow::Result<()> {
+    static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+
+    let params = ShowDocumentParams {
+        uri,
+        external: None,
+        take_focus: Some(true),
+        selection: None,
+    };
+    con.sender.send(Message::Request(Request::new(
+        RequestId::from(format!("trunkls/showDocument/{id}")),
+        ShowDocument::METHOD.to_string(),
+        params,
+    )))?;
+
+    Ok(())
+}
+
+/// Describes the package and the features it is built with, e.g. `app 0.1.0 · features: default`.
+fn crate_info(cargo_toml: &Path, data: &Value) -> String {
+    let doc = match std::fs::read_to_string(cargo_toml)
+        .map_err(anyhow::Error::from)
+        .and_then(|s| Ok(s.parse::<DocumentMut>()?))
+    {
+        Ok(doc) => doc,
+        Err(err) => return format!("Unable to read Cargo.toml: {err}"),
+    };
+    let Some(package) = doc.get("package") else {
+        return "Cargo.toml has no package".to_string();
+    };
+
+    let mut info = package
+        .get("name")
+        .and_then(|name| name.as_str())
+        .unwrap_or("unnamed")
+        .to_string();
+    // Versions inherited from the workspace are not resolved.
+    if let Some(version) = package.get("version").and_then(|version| version.as_str()) {
+        info.push(' ');
+        info.push_str(version);
+    }
+
+    if data["allFeatures"].as_bool() == Some(true) {
+        return format!("{info} · all features");
+    }
+    let mut features: Vec<String> = vec![];
+    if data["noDefaultFeatures"].as_bool() != Some(true) {
+        if let Some(default) = doc
+            .get("features")
+            .and_then(|features| features.get("default"))
+            .and_then(|default| default.as_array())
+        {
+            features.extend(
+                default
+                    .iter()
+                    .filter_map(|feature| feature.as_str())
+                    .map(|feature| format!("{feature} (default)")),
+            );
+        }
+    }
+    if let Some(explicit) = data["features"].as_str() {
+        features.extend(
+            explicit
+                .split([',', ' '])
+                .filter(|feature| !feature.is_empty())
+                .map(str::to_string),
+        );
+    }
+
+    match features.is_empty() {
+        true => format!("{info} · no features"),
+        false => format!("{info} · features: {}", features.join(", ")),
+    }
+}
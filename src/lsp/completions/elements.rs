@@ -0,0 +1,248 @@
+use std::str::FromStr;
+
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionResponse, InsertTextFormat, Position,
+};
+use tree_sitter::{Node, Point};
+
+use crate::{
+    attr_state::AssetType,
+    init::ClientCaps,
+    lsp::docs::{self, Script, ValueRequirment},
+    utils::point_range,
+};
+use texter::{change::GridIndex, core::text::Text};
+
+use super::{choice_placeholder, completion_edit, fuzzy_score};
+
+/// The prefix of the labels of element completions.
+const PREFIX: &str = "trunk-";
+
+/// Node kinds that cannot contain a new element.
+const NON_CONTENT_KINDS: &[&str] = &[
+    "start_tag",
+    "self_closing_tag",
+    "end_tag",
+    "erroneous_end_tag",
+    "attribute",
+    "raw_text",
+    "comment",
+    "doctype",
+];
+
+/// Completes whole Trunk elements with their required attributes, e.g. `trunk-css`, when typing
+/// in element content.
+///
+/// Only words that are a prefix of `trunk-` or start with it are completed, the part after it is
+/// matched fuzzily against the asset types.
+pub fn complete_element(pos: GridIndex, n: Node, text: &Text) -> Option<CompletionResponse> {
+    let s = text.text.as_str();
+    let bytes = s.as_bytes();
+    let row_start = text.br_indexes.row_start(pos.row)?;
+    let byte_pos = row_start + pos.col;
+
+    let is_word = |b: &&u8| b.is_ascii_alphanumeric() || **b == b'-';
+    let word_start = byte_pos
+        - bytes[row_start..byte_pos]
+            .iter()
+            .rev()
+            .take_while(is_word)
+            .count();
+    if word_start == byte_pos {
+        return None;
+    }
+    let word = s[word_start..byte_pos].to_ascii_lowercase();
+    let query = match word.strip_prefix(PREFIX) {
+        Some(query) => query,
+        None if PREFIX.starts_with(&word) => "",
+        None => return None,
+    };
+    let word_end = byte_pos + bytes[byte_pos..].iter().take_while(is_word).count();
+    // An opening bracket typed before the name is replaced as well.
+    let opened = word_start > row_start && bytes[word_start - 1] == b'<';
+
+    let word_point = Point::new(pos.row, word_start - row_start);
+    let in_pos = n.descendant_for_point_range(word_point, word_point)?;
+    // A name typed after `<` is parsed as the tag name of a new element.
+    let is_new_tag = opened && in_pos.kind() == "tag_name";
+    if !is_new_tag && !is_content(in_pos) {
+        return None;
+    }
+
+    let start = Point::new(pos.row, word_start - row_start - opened as usize);
+    let range = point_range(text, start, Point::new(pos.row, word_end - row_start))?;
+    let cursor = {
+        let mut pos = pos;
+        pos.denormalize(text).ok()?;
+        Position::from(pos)
+    };
+    let snippets = ClientCaps::get().snippets;
+
+    let links = AssetType::NAMES.iter().filter_map(|name| {
+        let asset = AssetType::from_str(name).ok()?;
+        let element = link_element(name, asset.to_required(), snippets);
+        let detail = link_element(name, asset.to_required(), false);
+        Some((*name, element, detail, asset.to_doc()))
+    });
+    let script = (
+        "script",
+        script_element(snippets),
+        script_element(false),
+        Script::as_str(),
+    );
+
+    let comps = links
+        .chain([script])
+        .filter_map(|(name, element, detail, doc)| {
+            fuzzy_score(query, name)?;
+            let label = String::from_iter([PREFIX, name]);
+            let filter_text = match opened {
+                true => String::from_iter(["<", &label]),
+                false => label.clone(),
+            };
+            Some(CompletionItem {
+                label,
+                kind: Some(CompletionItemKind::SNIPPET),
+                detail: Some(detail),
+                documentation: Some(docs::completion_docs(doc)),
+                filter_text: Some(filter_text),
+                insert_text_format: Some(match snippets {
+                    true => InsertTextFormat::SNIPPET,
+                    false => InsertTextFormat::PLAIN_TEXT,
+                }),
+                text_edit: Some(completion_edit(range, cursor, element)),
+                ..Default::default()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if comps.is_empty() {
+        return None;
+    }
+
+    Some(CompletionResponse::Array(comps))
+}
+
+/// Whether an element can be inserted at the node.
+fn is_content(mut n: Node) -> bool {
+    loop {
+        if NON_CONTENT_KINDS.contains(&n.kind()) {
+            return false;
+        }
+        match n.parent() {
+            Some(parent) => n = parent,
+            None => return true,
+        }
+    }
+}
+
+fn link_element(rel: &str, required: &[(&str, &str, ValueRequirment)], snippets: bool) -> String {
+    let attrs = required_attrs(required, snippets);
+    format!("<link data-trunk rel=\"{rel}\"{attrs}/>")
+}
+
+fn script_element(snippets: bool) -> String {
+    let attrs = required_attrs(Script::REQUIRED_ASSET_ATTRS, snippets);
+    format!("<script data-trunk{attrs}></script>")
+}
+
+/// The required attributes with a tab stop for each value when snippets are supported.
+fn required_attrs(required: &[(&str, &str, ValueRequirment)], snippets: bool) -> String {
+    let mut attrs = String::new();
+    for (i, (attr, _, req)) in required.iter().enumerate() {
+        let tab_stop = i + 1;
+        let value = match req {
+            _ if !req.must_have_value() => None,
+            ValueRequirment::Values(_, vals) if snippets => {
+                Some(choice_placeholder(tab_stop, vals))
+            }
+            _ if snippets => Some(format!("${tab_stop}")),
+            _ => Some(String::new()),
+        };
+        attrs.push(' ');
+        attrs.push_str(attr);
+        if let Some(value) = value {
+            attrs.push_str("=\"");
+            attrs.push_str(&value);
+            attrs.push('"');
+        }
+    }
+
+    attrs
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{
+        CompletionItem, CompletionResponse, CompletionTextEdit, InsertTextFormat, Position, Range,
+        TextEdit,
+    };
+    use texter::{change::GridIndex, core::text::Text};
+    use tree_sitter::{Parser, Point};
+
+    use super::complete_element;
+
+    /// The labels of the element completions at the end of the HTML.
+    fn labels(html: &str) -> Vec<String> {
+        let point = Point::new(html.lines().count() - 1, html.lines().last().unwrap().len());
+        completions(html, point)
+            .into_iter()
+            .map(|comp| comp.label)
+            .collect()
+    }
+
+    /// The `trunk-css` completion at the point, without snippet support.
+    fn css_completion(html: &str, point: Point) -> Option<CompletionItem> {
+        completions(html, point)
+            .into_iter()
+            .find(|comp| comp.label == "trunk-css")
+    }
+
+    fn completions(html: &str, point: Point) -> Vec<CompletionItem> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_html::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(html, None).unwrap();
+        let text = Text::new(html.to_string());
+        match complete_element(GridIndex::from(point), tree.root_node(), &text) {
+            Some(CompletionResponse::Array(comps)) => comps,
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn only_trunk_words_are_completed() {
+        assert!(labels("<p>a").is_empty());
+        assert!(labels("<body>\n<li").is_empty());
+        assert!(labels("<p>run").is_empty());
+        assert_eq!(labels("<p>tru").len(), 10);
+        assert_eq!(labels("<p>trunk-").len(), 10);
+        assert_eq!(labels("<p>trunk-rs"), ["trunk-rust"]);
+        assert!(labels("<p>trunk-rs-x").is_empty());
+    }
+
+    #[test]
+    fn element_replacement_ranges() {
+        let expected = |start| {
+            Some(CompletionTextEdit::Edit(TextEdit {
+                range: Range::new(Position::new(1, start), Position::new(1, 11)),
+                new_text: r#"<link data-trunk rel="css" href=""/>"#.to_string(),
+            }))
+        };
+
+        let opened = css_completion("<body>\n  <trunk-cs\n</body>", Point::new(1, 11)).unwrap();
+        assert_eq!(opened.text_edit, expected(2));
+        assert_eq!(opened.filter_text.as_deref(), Some("<trunk-css"));
+        assert_eq!(
+            opened.insert_text_format,
+            Some(InsertTextFormat::PLAIN_TEXT)
+        );
+
+        let bare = css_completion("<body>\n   trunk-cs\n</body>", Point::new(1, 11)).unwrap();
+        assert_eq!(bare.text_edit, expected(3));
+        assert_eq!(bare.filter_text.as_deref(), Some("trunk-css"));
+
+        assert!(css_completion("<link trunk-cs>", Point::new(0, 14)).is_none());
+    }
+}
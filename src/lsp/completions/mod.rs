@@ -1,13 +1,20 @@
+mod elements;
+
+use std::path::Path;
+
 use lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionResponse, Documentation, InsertTextFormat,
-    MarkupContent, MarkupKind,
+    Command, CompletionItem, CompletionItemKind, CompletionResponse, CompletionTextEdit,
+    InsertReplaceEdit, InsertTextFormat, Position, Range, TextEdit,
 };
 use streaming_iterator::{IntoStreamingIterator, StreamingIterator};
-use tree_sitter::{Node, QueryCursor};
+use tree_sitter::{Node, Point, QueryCursor};
 
 use crate::{
-    attr_state::{AssetType, TagName, TrunkAttrState},
-    utils::{find_attr, is_attr_name_completion, is_attr_value_completion},
+    attr_state::{AssetType, Attr, TagName, TrunkAttrState},
+    init::ClientCaps,
+    utils::{
+        find_attr, is_attr_name_completion, is_attr_value_completion, node_range, point_range,
+    },
 };
 use texter::{change::GridIndex, core::text::Text};
 
@@ -17,15 +24,26 @@ use super::{
 };
 
 impl TrunkAttrState {
-    fn to_completion(&self, s: &str, original: Node) -> Option<CompletionResponse> {
+    fn to_completion(
+        &self,
+        text: &Text,
+        original: Node,
+        cursor: Position,
+    ) -> Option<CompletionResponse> {
         match self.tag_name {
-            TagName::Link => self.link_to_completion(s, original),
-            TagName::Script => self.script_to_completion(s, original),
+            TagName::Link => self.link_to_completion(text, original, cursor),
+            TagName::Script => self.script_to_completion(text, original, cursor),
             TagName::Unknown => None,
         }
     }
 
-    fn script_to_completion(&self, s: &str, original: Node) -> Option<CompletionResponse> {
+    fn script_to_completion(
+        &self,
+        text: &Text,
+        original: Node,
+        cursor: Position,
+    ) -> Option<CompletionResponse> {
+        let s = text.text.as_str();
         if self.is_data_trunk_attr(s, original) {
             return Some(CompletionResponse::Array(vec![
                 docs::DataTrunk::completion(),
@@ -36,11 +54,12 @@ impl TrunkAttrState {
 
         if is_attr_name_completion(original.kind()) {
             let tag = attr_node.parent()?;
-            let mut cursor = tag.walk();
+            let mut tree_cursor = tag.walk();
             let attr_names: Vec<&str> = tag
-                .named_children(&mut cursor)
+                .named_children(&mut tree_cursor)
                 .filter_map(|n| {
-                    if n.kind() != "attribute" {
+                    // The attribute being typed is not yet present.
+                    if n.kind() != "attribute" || n == attr_node {
                         return None;
                     }
 
@@ -50,7 +69,7 @@ impl TrunkAttrState {
                         .ok()
                 })
                 .collect();
-            return self.complete_script_attr_name(s, attr_names, original);
+            return self.complete_attr_name(text, attr_names, attr_node, cursor);
         }
 
         if is_attr_value_completion(original.kind()) {
@@ -60,7 +79,13 @@ impl TrunkAttrState {
         None
     }
 
-    fn link_to_completion(&self, s: &str, original: Node) -> Option<CompletionResponse> {
+    fn link_to_completion(
+        &self,
+        text: &Text,
+        original: Node,
+        cursor: Position,
+    ) -> Option<CompletionResponse> {
+        let s = text.text.as_str();
         if self.is_data_trunk_attr(s, original) {
             return Some(CompletionResponse::Array(vec![
                 docs::DataTrunk::completion(),
@@ -91,11 +116,12 @@ impl TrunkAttrState {
 
         if is_attr_name_completion(original.kind()) {
             let tag = attr_node.parent()?;
-            let mut cursor = tag.walk();
+            let mut tree_cursor = tag.walk();
             let attr_names: Vec<&str> = tag
-                .named_children(&mut cursor)
+                .named_children(&mut tree_cursor)
                 .filter_map(|n| {
-                    if n.kind() != "attribute" {
+                    // The attribute being typed is not yet present.
+                    if n.kind() != "attribute" || n == attr_node {
                         return None;
                     }
 
@@ -105,7 +131,7 @@ impl TrunkAttrState {
                         .ok()
                 })
                 .collect();
-            return self.complete_link_attr_name(s, attr_names, attr_name_node, asset_type);
+            return self.complete_attr_name(text, attr_names, attr_node, cursor);
         };
 
         if is_attr_value_completion(original.kind()) {
@@ -125,52 +151,99 @@ impl TrunkAttrState {
                 .is_ok_and(|s| s.starts_with("data-"))
     }
 
-    /// Accepts a node with a kind of "attribute_name".
-    pub fn complete_link_attr_name(
+    /// Completes the names of the attributes accepted by the asset that are not present yet.
+    ///
+    /// The typed name is matched as a subsequence, as all Trunk attributes share the `data-`
+    /// prefix. Better matches are ranked first, then required and commonly used attributes.
+    fn complete_attr_name(
         &self,
-        s: &str,
+        text: &Text,
         attr_names: Vec<&str>,
-        attr_name_node: Node,
-        asset_type: AssetType,
+        attr_node: Node,
+        cursor: Position,
     ) -> Option<CompletionResponse> {
+        let s = text.text.as_str();
+        let attr_name_node = attr_node
+            .named_child(0)
+            .filter(|n| n.kind() == "attribute_name")?;
         let attr_name_str = attr_name_node.utf8_text(s.as_bytes()).ok()?;
-        let comps = asset_type
-            .to_info()
+        let range = node_range(text, attr_name_node)?;
+        // Only the name is replaced if the attribute already has a value.
+        let has_value = attr_node.named_child_count() > 1;
+        let required = self.required_attrs();
+        let snippets = ClientCaps::get().snippets;
+
+        let mut comps: Vec<(String, CompletionItem)> = self
+            .asset_attrs()?
             .iter()
-            .filter_map(|(attr, doc, req): &(&str, &str, ValueRequirment)| {
-                if (!attr.starts_with(attr_name_str)) || attr_names.contains(attr) {
-                    return None;
-                }
-
-                let insert_kind;
-                let kind;
-                let f_attr;
-                if req.must_have_value() {
-                    kind = Some(CompletionItemKind::SNIPPET);
-                    insert_kind = InsertTextFormat::SNIPPET;
-                    f_attr = String::from_iter([attr, "=\"$0\""]);
+            .enumerate()
+            .filter(|(_, (attr, _, _))| !attr_names.contains(attr))
+            .filter_map(|(i, (attr, doc, req))| {
+                let score = fuzzy_score(attr_name_str, attr)?;
+                let rank = if required.iter().any(|(r, _, _)| r == attr) {
+                    0
+                } else if COMMON_ATTRS.contains(attr) {
+                    1
                 } else {
-                    kind = None;
-                    insert_kind = InsertTextFormat::PLAIN_TEXT;
-                    f_attr = attr.to_string();
+                    2
+                };
+
+                let (kind, insert_kind, new_text, command) = match req {
+                    _ if has_value || !req.must_have_value() => {
+                        (None, InsertTextFormat::PLAIN_TEXT, attr.to_string(), None)
+                    }
+                    // The value is picked from a choice placeholder, saving another completion.
+                    ValueRequirment::Values(_, vals) if snippets => (
+                        Some(CompletionItemKind::SNIPPET),
+                        InsertTextFormat::SNIPPET,
+                        format!("{attr}=\"{}\"", choice_placeholder(1, vals)),
+                        None,
+                    ),
+                    ValueRequirment::Values(..) => (
+                        None,
+                        InsertTextFormat::PLAIN_TEXT,
+                        String::from_iter([attr, "=\"\""]),
+                        Some(trigger_suggest()),
+                    ),
+                    _ if snippets => (
+                        Some(CompletionItemKind::SNIPPET),
+                        InsertTextFormat::SNIPPET,
+                        String::from_iter([attr, "=\"$0\""]),
+                        None,
+                    ),
+                    _ => (
+                        None,
+                        InsertTextFormat::PLAIN_TEXT,
+                        String::from_iter([attr, "=\"\""]),
+                        None,
+                    ),
                 };
 
-                Some(CompletionItem {
+                let sort_text = sort_text(rank, score, i);
+                let comp = CompletionItem {
                     kind,
-                    label: f_attr,
-                    documentation: Some(Documentation::MarkupContent(MarkupContent {
-                        kind: MarkupKind::Markdown,
-                        value: doc.to_string(),
-                    })),
+                    label: attr.to_string(),
+                    documentation: Some(docs::completion_docs(doc)),
+                    filter_text: Some(attr.to_string()),
+                    sort_text: Some(sort_text.clone()),
                     insert_text_format: Some(insert_kind),
+                    text_edit: Some(completion_edit(range, cursor, new_text)),
+                    command,
                     ..Default::default()
-                })
+                };
+                Some((sort_text, comp))
             })
             .collect();
 
-        Some(CompletionResponse::Array(comps))
-    }
+        comps.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if let Some((_, best)) = comps.first_mut() {
+            best.preselect = Some(true);
+        }
 
+        Some(CompletionResponse::Array(
+            comps.into_iter().map(|(_, comp)| comp).collect(),
+        ))
+    }
     /// Accepts a node with a kind of "attribute_value".
     fn complete_link_attr_value(
         &self,
@@ -188,10 +261,7 @@ impl TrunkAttrState {
                 |(attr_name, _, req)| match (req, attr_name_str == *attr_name) {
                     (ValueRequirment::Values(_, accepts), true) => {
                         Some(accepts.iter().map(|(val, doc)| CompletionItem {
-                            documentation: Some(Documentation::MarkupContent(MarkupContent {
-                                kind: MarkupKind::Markdown,
-                                value: doc.to_string(),
-                            })),
+                            documentation: Some(docs::completion_docs(doc)),
                             label: val.to_string(),
                             ..Default::default()
                         }))
@@ -203,31 +273,6 @@ impl TrunkAttrState {
         Some(CompletionResponse::Array(comps.collect()))
     }
 
-    fn complete_script_attr_name(
-        &self,
-        s: &str,
-        attr_names: Vec<&str>,
-        attr_name_node: Node,
-    ) -> Option<CompletionResponse> {
-        let attr_name_str = attr_name_node.utf8_text(s.as_bytes()).ok()?;
-        let comps = Script::ASSET_ATTRS
-            .iter()
-            .filter(|(attr_name, _, _)| {
-                attr_name.starts_with(attr_name_str) && !attr_names.contains(attr_name)
-            })
-            .map(|(attr_name, doc, _)| CompletionItem {
-                label: attr_name.to_string(),
-                documentation: Some(Documentation::MarkupContent(MarkupContent {
-                    kind: MarkupKind::Markdown,
-                    value: doc.to_string(),
-                })),
-                ..Default::default()
-            })
-            .collect();
-
-        Some(CompletionResponse::Array(comps))
-    }
-
     fn complete_script_attr_value(
         &self,
         s: &str,
@@ -242,10 +287,7 @@ impl TrunkAttrState {
                     vals.iter()
                         .map(|(val, doc)| CompletionItem {
                             label: val.to_string(),
-                            documentation: Some(Documentation::MarkupContent(MarkupContent {
-                                value: doc.to_string(),
-                                kind: MarkupKind::Markdown,
-                            })),
+                            documentation: Some(docs::completion_docs(doc)),
                             ..Default::default()
                         })
                         .collect(),
@@ -256,7 +298,151 @@ impl TrunkAttrState {
     }
 }
 
-pub fn completions(pos: GridIndex, n: Node, text: &Text) -> Option<CompletionResponse> {
+/// Replaces the range with the text, clients supporting it may only insert the text before the
+/// cursor instead.
+fn completion_edit(range: Range, cursor: Position, new_text: String) -> CompletionTextEdit {
+    let in_range = range.start <= cursor && cursor <= range.end;
+    if !ClientCaps::get().insert_replace || !in_range {
+        return CompletionTextEdit::Edit(TextEdit { range, new_text });
+    }
+
+    CompletionTextEdit::InsertAndReplace(InsertReplaceEdit {
+        new_text,
+        insert: Range {
+            start: range.start,
+            end: cursor,
+        },
+        replace: range,
+    })
+}
+
+/// A snippet placeholder offering the values to choose from.
+fn choice_placeholder(tab_stop: usize, vals: &[(&str, &str)]) -> String {
+    let choices: Vec<&str> = vals.iter().map(|(val, _)| *val).collect();
+    format!("${{{tab_stop}|{}|}}", choices.join(","))
+}
+
+/// Optional attributes that are ranked before the other optional attributes.
+const COMMON_ATTRS: &[&str] = &[
+    "data-bin",
+    "data-type",
+    "data-cargo-features",
+    "data-target-path",
+    "data-integrity",
+];
+
+/// Asks the client to request completions again, used to complete a value right after its
+/// attribute was inserted. Clients that don't know the command ignore it.
+fn trigger_suggest() -> Command {
+    Command {
+        title: String::from("Suggest values"),
+        command: String::from("editor.action.triggerSuggest"),
+        arguments: None,
+    }
+}
+
+/// Orders attribute completions by their rank, then how well they match and lastly their
+/// position in the schema.
+fn sort_text(rank: u8, score: usize, i: usize) -> String {
+    format!("{rank}{score:03}{i:03}")
+}
+
+/// Matches the query as a case insensitive subsequence of the attribute name.
+///
+/// Lower scores are better matches, every skipped character between two matched characters and
+/// a match starting inside a `-` separated segment count against it.
+fn fuzzy_score(query: &str, attr: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut last = None;
+    let mut chars = attr.char_indices();
+    for q in query.chars().map(|c| c.to_ascii_lowercase()) {
+        let (i, _) = chars.by_ref().find(|(_, c)| c.to_ascii_lowercase() == q)?;
+        match last {
+            Some(last) => score += i - last - 1,
+            None if i != 0 && !attr[..i].ends_with('-') => score += 1,
+            None => {}
+        }
+        last = Some(i);
+    }
+
+    Some(score)
+}
+
+/// The maximum depth of directories suggested for `data-target-path` values.
+const TARGET_PATH_DEPTH: usize = 4;
+
+/// Completes the existing directories inside the dist directory for a `data-target-path` value.
+///
+/// Accepts a node with a kind of "attribute_value" or "quoted_attribute_value".
+fn complete_target_path(
+    text: &Text,
+    in_pos: Node,
+    dist: &Path,
+    cursor: Position,
+) -> Option<CompletionResponse> {
+    let range = match (in_pos.kind(), in_pos.named_child(0)) {
+        ("attribute_value", _) => node_range(text, in_pos)?,
+        (_, Some(val)) => node_range(text, val)?,
+        // An empty quoted value, the range is between the quotes.
+        _ => {
+            let start = in_pos.start_position();
+            let start = Point::new(start.row, start.column + 1);
+            point_range(text, start, start)?
+        }
+    };
+
+    let mut dirs = vec![];
+    collect_dirs(dist, "", TARGET_PATH_DEPTH, &mut dirs);
+    dirs.sort();
+
+    let comps = dirs
+        .into_iter()
+        .map(|dir| CompletionItem {
+            label: dir.clone(),
+            kind: Some(CompletionItemKind::FOLDER),
+            text_edit: Some(completion_edit(range, cursor, dir)),
+            ..Default::default()
+        })
+        .collect();
+
+    Some(CompletionResponse::Array(comps))
+}
+
+fn collect_dirs(dir: &Path, prefix: &str, depth: usize, dirs: &mut Vec<String>) {
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = dir.read_dir() else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if !entry.file_type().is_ok_and(|ft| ft.is_dir()) {
+            continue;
+        }
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let rel = String::from_iter([prefix, &name]);
+        collect_dirs(
+            &entry.path(),
+            &String::from_iter([&rel, "/"]),
+            depth - 1,
+            dirs,
+        );
+        dirs.push(rel);
+    }
+}
+
+pub fn completions(
+    pos: GridIndex,
+    n: Node,
+    text: &Text,
+    dist: Option<&Path>,
+) -> Option<CompletionResponse> {
+    if let Some(comps) = elements::complete_element(pos, n, text) {
+        return Some(comps);
+    }
+
     let s = text.text.as_str();
     let mut cursor = QueryCursor::new();
     let element_id = TRUNK_ATTRS
@@ -292,19 +478,85 @@ pub fn completions(pos: GridIndex, n: Node, text: &Text) -> Option<CompletionRes
     }
 
     // If the end of the found node is " we shouldn't return a completion as the cursor is after a
-    // quote.
+    // quote, unless the value is empty. Clients without snippets insert `attr=""` and ask for the
+    // value completions right after it.
     let byte_pos = text.br_indexes.row_start(pos.row).unwrap() + pos.col;
     let prev_byte = s.as_bytes()[byte_pos.saturating_sub(1)];
-    if matches!(prev_byte, b'\'' | b'"')
+    let after_quote = matches!(prev_byte, b'\'' | b'"')
         && in_pos.kind() == "quoted_attribute_value"
-        && byte_pos == in_pos.end_byte()
-    {
+        && byte_pos == in_pos.end_byte();
+    let after_empty_value = after_quote && in_pos.named_child_count() == 0;
+    if after_quote && !after_empty_value {
         return None;
     }
 
-    let mut cursor = current.node.walk();
-    let children = current.node.named_children(&mut cursor);
+    let mut tree_cursor = current.node.walk();
+    let children = current.node.named_children(&mut tree_cursor);
     let attr_state = TrunkAttrState::from_elem_items(s, children)?;
+    let cursor = {
+        let mut pos = pos;
+        pos.denormalize(text).ok()?;
+        Position::from(pos)
+    };
+
+    if let Some(dist) = dist.filter(|_| is_attr_value_completion(in_pos.kind())) {
+        let is_target_path = find_attr(in_pos)
+            .and_then(|attr| Attr::from_node(s, attr))
+            .is_some_and(|attr| attr.name == "data-target-path");
+        let accepts_target_path = attr_state
+            .asset_attrs()
+            .is_some_and(|attrs| attrs.iter().any(|(a, _, _)| *a == "data-target-path"));
+        if is_target_path && accepts_target_path {
+            return complete_target_path(text, in_pos, dist, cursor);
+        }
+    }
+
+    let comps = attr_state.to_completion(text, in_pos, cursor)?;
+    if !after_empty_value {
+        return Some(comps);
+    }
 
-    attr_state.to_completion(s, in_pos)
+    // The values are inserted between the quotes by replacing the whole empty value.
+    let range = node_range(text, in_pos)?;
+    let CompletionResponse::Array(comps) = comps else {
+        return Some(comps);
+    };
+    let comps = comps
+        .into_iter()
+        .map(|comp| {
+            let new_text = format!("\"{}\"", comp.label);
+            CompletionItem {
+                // Clients filter with the replaced text, which includes the quotes.
+                filter_text: Some(new_text.clone()),
+                text_edit: Some(completion_edit(range, cursor, new_text)),
+                ..comp
+            }
+        })
+        .collect();
+    Some(CompletionResponse::Array(comps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_score, sort_text};
+
+    #[test]
+    fn fuzzy_matching() {
+        assert_eq!(fuzzy_score("feat", "data-cargo-features"), Some(0));
+        assert_eq!(fuzzy_score("", "href"), Some(0));
+        assert_eq!(fuzzy_score("FEAT", "data-cargo-features"), Some(0));
+        assert!(fuzzy_score("dcf", "data-cargo-features").is_some());
+        assert!(
+            fuzzy_score("feat", "data-cargo-no-default-features")
+                > fuzzy_score("feat", "data-cargo-features")
+        );
+        assert_eq!(fuzzy_score("xyz", "data-bin"), None);
+
+        // A required attribute is listed before a common one that matches better.
+        assert!(fuzzy_score("f", "href") > fuzzy_score("f", "data-cargo-features"));
+        let required = sort_text(0, fuzzy_score("f", "href").unwrap(), 5);
+        let common = sort_text(1, fuzzy_score("f", "data-cargo-features").unwrap(), 0);
+        assert!(required < common);
+        assert!(sort_text(1, 0, 9) < sort_text(1, 1, 0));
+    }
 }
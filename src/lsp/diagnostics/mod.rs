@@ -0,0 +1,736 @@
+use std::path::Path;
+
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString,
+    TextEdit, Uri,
+};
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::{AssetType, Attr, ElemAttrs, TagName, TrunkAttrState},
+    lsp::docs::ValueRequirment,
+    utils::{edit_distance, insert_attr_edit, node_range, remove_attr_edit, uri_to_path},
+};
+use texter::core::text::Text;
+
+use super::{
+    pipeline::{infer_inline_type, HASH},
+    queries::attributes::{link_script_elements, trunk_elements},
+    trunk_toml::filehash,
+};
+
+/// A diagnostic along with the quick fixes that resolve it.
+pub struct Problem {
+    pub diagnostic: Diagnostic,
+    pub fixes: Vec<Fix>,
+}
+
+/// A named set of edits to the document a problem was found in.
+pub struct Fix {
+    pub title: String,
+    pub edits: Vec<TextEdit>,
+}
+
+impl Problem {
+    /// Adds a fix, unless none of its edits could be created.
+    fn with_fix(mut self, title: String, edits: impl IntoIterator<Item = TextEdit>) -> Self {
+        let edits: Vec<TextEdit> = edits.into_iter().collect();
+        if !edits.is_empty() {
+            self.fixes.push(Fix { title, edits });
+        }
+        self
+    }
+}
+
+/// Returns the diagnostics for all Trunk elements in the document.
+pub fn diagnostics(uri: &Uri, n: Node, text: &Text) -> Vec<Diagnostic> {
+    problems(uri, n, text)
+        .into_iter()
+        .map(|problem| problem.diagnostic)
+        .collect()
+}
+
+/// Returns the problems for all Trunk elements in the document, along with link and script
+/// elements that are likely meant to be Trunk elements.
+pub fn problems(uri: &Uri, n: Node, text: &Text) -> Vec<Problem> {
+    let s = text.text.as_str();
+    // Paths of assets are relative to the directory of the HTML file.
+    let html = uri_to_path(uri);
+    let dir = html
+        .as_ref()
+        .and_then(|html| Some(html.parent()?.to_path_buf()));
+    let hash_names = html.as_deref().is_none_or(filehash);
+    let mut diags = vec![];
+    let mut outputs = vec![];
+    for elem in trunk_elements(n, s) {
+        let mut cursor = elem.walk();
+        let Some(attr_state) = TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))
+        else {
+            continue;
+        };
+        let attrs = ElemAttrs::from_elem(s, elem);
+        attr_state.schema_diagnostics(text, elem, &attrs, &mut diags);
+        attr_state.inline_diagnostics(text, &attrs, &mut diags);
+        target_path_diagnostics(text, &attrs, &mut diags);
+        if let Some(dir) = &dir {
+            attr_state.copy_diagnostics(text, dir, &attrs, &mut diags);
+        }
+
+        if let Some(output) = attr_state.output(elem, &attrs, hash_names) {
+            outputs.push(output);
+        }
+    }
+    collision_diagnostics(uri, text, &outputs, &mut diags);
+
+    for elem in link_script_elements(n, s) {
+        data_trunk_typo_diagnostics(text, &ElemAttrs::from_elem(s, elem), &mut diags);
+    }
+
+    diags
+}
+
+/// Pairs of attributes of a `rel="rust"` asset that cannot be used together.
+const RUST_CONFLICTS: &[(&str, &str)] = &[
+    ("data-cargo-all-features", "data-cargo-features"),
+    ("data-cargo-all-features", "data-cargo-no-default-features"),
+];
+
+/// The largest edit distance at which an attribute name is considered a misspelling of the
+/// candidate.
+///
+/// Short names such as `data-trunk` are a single edit away from real attributes like
+/// `data-track`, so they only allow one.
+fn max_typo_distance(candidate: &str) -> usize {
+    match candidate.len() {
+        ..=12 => 1,
+        _ => 2,
+    }
+}
+
+/// Returns the name closest to the misspelt name, if it is close enough to be a typo.
+fn closest_name<'a>(name: &str, names: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    names
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= max_typo_distance(candidate))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Returns the edits that replace the name of an attribute.
+fn rename_attr_edit(text: &Text, attr: &Attr, name: &str) -> Option<TextEdit> {
+    let name_node = attr.node.named_child(0)?;
+    Some(TextEdit {
+        range: node_range(text, name_node)?,
+        new_text: name.to_string(),
+    })
+}
+
+/// Reports link and script elements that are ignored by Trunk because `data-trunk` is misspelt.
+fn data_trunk_typo_diagnostics(text: &Text, attrs: &ElemAttrs, diags: &mut Vec<Problem>) {
+    if attrs.has("data-trunk") {
+        return;
+    }
+    for attr in attrs.attrs.iter() {
+        if closest_name(attr.name, ["data-trunk"].into_iter()).is_none() {
+            continue;
+        }
+        diags.extend(
+            warning(
+                text,
+                attr.node,
+                "possible-typo",
+                format!(
+                    "`{}` looks like a misspelling of `data-trunk`, Trunk does not process this element.",
+                    attr.name
+                ),
+            )
+            .map(|problem| {
+                problem.with_fix(
+                    "Replace with `data-trunk`".to_string(),
+                    rename_attr_edit(text, attr, "data-trunk"),
+                )
+            }),
+        );
+    }
+}
+
+/// Creates a warning problem with the provided code.
+fn warning(text: &Text, n: Node, code: &str, message: String) -> Option<Problem> {
+    Some(Problem {
+        diagnostic: Diagnostic {
+            range: node_range(text, n)?,
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String(code.to_string())),
+            source: Some("trunkls".to_string()),
+            message,
+            ..Default::default()
+        },
+        fixes: vec![],
+    })
+}
+
+/// Creates an error problem with the provided code.
+fn error(text: &Text, n: Node, code: &str, message: String) -> Option<Problem> {
+    let mut problem = warning(text, n, code, message)?;
+    problem.diagnostic.severity = Some(DiagnosticSeverity::ERROR);
+    Some(problem)
+}
+
+/// Checks that a `data-target-path` value is a relative path that stays inside the dist directory.
+fn target_path_diagnostics(text: &Text, attrs: &ElemAttrs, diags: &mut Vec<Problem>) {
+    let Some(attr) = attrs.get("data-target-path") else {
+        return;
+    };
+    if let Err((code, message)) = validate_target_path(attr.value.unwrap_or_default()) {
+        let n = attr.value_node.unwrap_or(attr.node);
+        diags.extend(error(text, n, code, message.to_string()));
+    }
+}
+
+/// Validates a `data-target-path` value, returning the diagnostic code and message on failure.
+pub fn validate_target_path(path: &str) -> Result<(), (&'static str, &'static str)> {
+    if path.trim().is_empty() {
+        return Err((
+            "target-path-empty",
+            "`data-target-path` must not be empty, omit the attribute to use the dist root.",
+        ));
+    }
+
+    let bytes = path.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        return Err((
+            "target-path-drive",
+            "`data-target-path` must be a relative path, drive prefixes are not allowed.",
+        ));
+    }
+
+    if path.starts_with(['/', '\\']) {
+        return Err((
+            "target-path-absolute",
+            "`data-target-path` must be a relative path.",
+        ));
+    }
+
+    if path.split(['/', '\\']).any(|comp| comp == "..") {
+        return Err((
+            "target-path-parent",
+            "`data-target-path` must not contain `..` components.",
+        ));
+    }
+
+    Ok(())
+}
+
+/// The location an asset is written to inside the dist directory.
+struct Output<'a> {
+    /// The node diagnostics about the output are reported on.
+    node: Node<'a>,
+    /// The normalized components of the output path.
+    components: Vec<String>,
+    /// Whether the output is a directory.
+    is_dir: bool,
+    /// Whether the name of the output contains a content hash.
+    hashed: bool,
+}
+
+impl Output<'_> {
+    /// Whether writing both outputs would overwrite a file of the other.
+    ///
+    /// Hashed outputs only share a name if their content is the same, so they never collide.
+    fn collides_with(&self, other: &Self) -> bool {
+        if self.hashed || other.hashed {
+            return false;
+        }
+        let shorter = self.components.len().min(other.components.len());
+        if self.components[..shorter] != other.components[..shorter] {
+            return false;
+        }
+
+        match self.components.len().cmp(&other.components.len()) {
+            std::cmp::Ordering::Equal => true,
+            std::cmp::Ordering::Less => self.is_dir,
+            std::cmp::Ordering::Greater => other.is_dir,
+        }
+    }
+}
+
+/// Reports assets of the document that are written to the same location in the dist directory.
+fn collision_diagnostics(uri: &Uri, text: &Text, outputs: &[Output], diags: &mut Vec<Problem>) {
+    for (i, output) in outputs.iter().enumerate() {
+        let related: Vec<DiagnosticRelatedInformation> = outputs
+            .iter()
+            .enumerate()
+            .filter(|(j, other)| i != *j && output.collides_with(other))
+            .filter_map(|(_, other)| {
+                Some(DiagnosticRelatedInformation {
+                    location: Location {
+                        uri: uri.clone(),
+                        range: node_range(text, other.node)?,
+                    },
+                    message: "Also written here.".to_string(),
+                })
+            })
+            .collect();
+        if related.is_empty() {
+            continue;
+        }
+
+        let Some(mut problem) = warning(
+            text,
+            output.node,
+            "output-collision",
+            format!(
+                "The output `{}` overwrites or is overwritten by another asset in the dist directory.",
+                output.components.join("/")
+            ),
+        ) else {
+            continue;
+        };
+        problem.diagnostic.related_information = Some(related);
+        diags.push(problem);
+    }
+}
+
+impl TrunkAttrState {
+    /// Checks the attributes of the element against the schema of its asset type.
+    fn schema_diagnostics(
+        &self,
+        text: &Text,
+        elem: Node,
+        attrs: &ElemAttrs,
+        diags: &mut Vec<Problem>,
+    ) {
+        let Some(tag_name) = elem.named_child(0).filter(|n| n.kind() == "tag_name") else {
+            return;
+        };
+
+        if let TagName::Link = self.tag_name {
+            match attrs.get("rel") {
+                None => {
+                    diags.extend(
+                        error(
+                            text,
+                            tag_name,
+                            "missing-attribute",
+                            "Trunk link elements require a `rel` attribute.".to_string(),
+                        )
+                        .map(|problem| {
+                            problem.with_fix(
+                                "Insert `rel` attribute".to_string(),
+                                insert_attr_edit(text, elem, "rel=\"\""),
+                            )
+                        }),
+                    );
+                    return;
+                }
+                Some(rel) if self.rel.is_none() => {
+                    diags.extend(invalid_value(text, rel, AssetType::NAMES));
+                    return;
+                }
+                Some(_) => {}
+            }
+        }
+
+        let Some(asset_attrs) = self.asset_attrs() else {
+            return;
+        };
+
+        for (attr_name, _, req) in self.required_attrs() {
+            if attrs.has(attr_name) {
+                continue;
+            }
+            let attr_text = if req.must_have_value() {
+                format!("{attr_name}=\"\"")
+            } else {
+                attr_name.to_string()
+            };
+            diags.extend(
+                error(
+                    text,
+                    tag_name,
+                    "missing-attribute",
+                    format!("This asset requires the `{attr_name}` attribute."),
+                )
+                .map(|problem| {
+                    problem.with_fix(
+                        format!("Insert `{attr_name}` attribute"),
+                        insert_attr_edit(text, elem, &attr_text),
+                    )
+                }),
+            );
+        }
+
+        for attr in attrs.attrs.iter() {
+            let schema = asset_attrs.iter().find(|(name, _, _)| *name == attr.name);
+            match schema {
+                None if attr.name.starts_with("data-") && attr.name != "data-trunk" => {
+                    let names = asset_attrs.iter().map(|(name, _, _)| *name);
+                    let problem = match closest_name(attr.name, names) {
+                        Some(closest) => warning(
+                            text,
+                            attr.node,
+                            "possible-typo",
+                            format!(
+                                "`{}` is not a valid attribute for this asset, did you mean `{closest}`?",
+                                attr.name
+                            ),
+                        )
+                        .map(|problem| {
+                            problem.with_fix(
+                                format!("Replace with `{closest}`"),
+                                rename_attr_edit(text, attr, closest),
+                            )
+                        }),
+                        None => warning(
+                            text,
+                            attr.node,
+                            "invalid-attribute",
+                            format!("`{}` is not a valid attribute for this asset.", attr.name),
+                        ),
+                    };
+                    diags.extend(problem.map(|problem| {
+                        problem.with_fix(
+                            format!("Remove `{}` attribute", attr.name),
+                            remove_attr_edit(text, attr.node),
+                        )
+                    }));
+                }
+                Some((_, _, ValueRequirment::Values(_, vals))) => {
+                    let Some(val) = attr.value.filter(|val| !val.is_empty()) else {
+                        continue;
+                    };
+                    if vals.iter().all(|(v, _)| *v != val) {
+                        let vals: Vec<&str> = vals.iter().map(|(v, _)| *v).collect();
+                        diags.extend(invalid_value(text, attr, &vals));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let conflicts = match (self.tag_name, self.rel) {
+            (TagName::Link, Some(AssetType::Rust)) => RUST_CONFLICTS,
+            _ => &[],
+        };
+        for (first, second) in conflicts {
+            let (Some(first), Some(second)) = (attrs.get(first), attrs.get(second)) else {
+                continue;
+            };
+            let [first, second] = if first.node.start_byte() < second.node.start_byte() {
+                [first, second]
+            } else {
+                [second, first]
+            };
+            let Some(mut problem) = warning(
+                text,
+                second.node,
+                "conflicting-attributes",
+                format!(
+                    "`{}` cannot be used together with `{}`.",
+                    second.name, first.name
+                ),
+            ) else {
+                continue;
+            };
+            for attr in [first, second] {
+                problem = problem.with_fix(
+                    format!("Remove `{}` attribute", attr.name),
+                    remove_attr_edit(text, attr.node),
+                );
+            }
+            diags.push(problem);
+        }
+    }
+
+    /// Checks that `rel="copy-file"` does not point at a directory and `rel="copy-dir"` does not
+    /// point at a file.
+    fn copy_diagnostics(
+        &self,
+        text: &Text,
+        dir: &Path,
+        attrs: &ElemAttrs,
+        diags: &mut Vec<Problem>,
+    ) {
+        let (code, message, replacement) = match self.rel {
+            Some(AssetType::CopyFile) => (
+                "copy-file-directory",
+                "`copy-file` requires a file but the path is a directory.",
+                "copy-dir",
+            ),
+            Some(AssetType::CopyDir) => (
+                "copy-dir-file",
+                "`copy-dir` requires a directory but the path is a file.",
+                "copy-file",
+            ),
+            _ => return,
+        };
+        let (Some(href), Some(rel)) = (attrs.get("href"), attrs.get("rel")) else {
+            return;
+        };
+        let Some(path) = href.value.filter(|v| !v.is_empty()).map(|v| dir.join(v)) else {
+            return;
+        };
+        let mismatch = match self.rel {
+            Some(AssetType::CopyFile) => path.is_dir(),
+            _ => path.is_file(),
+        };
+        if !mismatch {
+            return;
+        }
+
+        let Some(mut problem) = warning(
+            text,
+            href.value_node.unwrap_or(href.node),
+            code,
+            message.to_string(),
+        ) else {
+            return;
+        };
+        let rel_range = rel
+            .content_node()
+            .and_then(|rel_val| node_range(text, rel_val));
+        if let Some(range) = rel_range {
+            // Both assets accept the same attributes, so nothing else has to change.
+            problem = problem.with_fix(
+                format!("Change to `rel=\"{replacement}\"`"),
+                [TextEdit {
+                    range,
+                    new_text: replacement.to_string(),
+                }],
+            );
+        }
+        diags.push(problem);
+    }
+
+    /// The output of the asset, `filehash` tells whether Trunk hashes the names of outputs.
+    fn output<'a>(
+        &self,
+        elem: Node<'a>,
+        attrs: &ElemAttrs<'a>,
+        filehash: bool,
+    ) -> Option<Output<'a>> {
+        let mut path = self.output_path(attrs)?;
+        if !filehash {
+            path = path.replace(&format!("-{HASH}"), "");
+        }
+        let components = path
+            .split(['/', '\\'])
+            .filter(|comp| !comp.is_empty() && *comp != ".")
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        if components.is_empty() {
+            return None;
+        }
+        let node = attrs
+            .get("href")
+            .or_else(|| attrs.get("src"))
+            .map(|attr| attr.node)
+            .unwrap_or(elem);
+
+        Some(Output {
+            node,
+            components,
+            is_dir: self.rel == Some(AssetType::CopyDir),
+            hashed: path.contains(HASH),
+        })
+    }
+
+    /// Checks that the type of a `rel="inline"` asset can be determined and that it matches the
+    /// extension of the file.
+    fn inline_diagnostics(&self, text: &Text, attrs: &ElemAttrs, diags: &mut Vec<Problem>) {
+        if self.rel != Some(AssetType::Inline) {
+            return;
+        }
+        let Some(href) = attrs.get("href") else {
+            return;
+        };
+        let inferred = href.value.and_then(infer_inline_type);
+
+        match (attrs.get("type"), inferred) {
+            (None, None) => diags.extend(warning(
+                text,
+                href.node,
+                "inline-type-unknown",
+                "The inline type cannot be inferred from the file extension, add a `type` attribute."
+                    .to_string(),
+            )),
+            (Some(ty), Some(inferred)) => {
+                let Some(ty_val) = ty.value.filter(|v| !v.is_empty()) else {
+                    return;
+                };
+                let Some(group) = inline_type_group(ty_val) else {
+                    // Unknown values are reported by the schema diagnostics.
+                    return;
+                };
+                if Some(group) != inline_type_group(inferred) {
+                    diags.extend(warning(
+                        text,
+                        ty.node,
+                        "inline-type-mismatch",
+                        format!(
+                            "The type `{ty_val}` contradicts the file extension which implies `{inferred}`."
+                        ),
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reports a value that is not one of the accepted values with a fix using the closest one.
+fn invalid_value(text: &Text, attr: &Attr, accepted: &[&str]) -> Option<Problem> {
+    let val = attr.value.unwrap_or_default();
+    let n = attr.value_node.unwrap_or(attr.node);
+    let mut problem = error(
+        text,
+        n,
+        "invalid-value",
+        format!(
+            "`{val}` is not a valid value for `{}`, expected one of {}.",
+            attr.name,
+            accepted
+                .iter()
+                .map(|v| String::from_iter(["`", v, "`"]))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    )?;
+
+    let closest = accepted.iter().min_by_key(|v| edit_distance(val, v));
+    // The edit replaces the content between the quotes, or the whole value if it is not quoted.
+    let val_range = attr.content_node().and_then(|val| node_range(text, val));
+    if let Some((closest, range)) = closest.zip(val_range) {
+        problem = problem.with_fix(
+            format!("Replace with `{closest}`"),
+            [TextEdit {
+                range,
+                new_text: closest.to_string(),
+            }],
+        );
+    }
+
+    Some(problem)
+}
+
+/// Groups inline types that produce the same kind of output.
+///
+/// Modules are emitted as `<script type="module">`, so they are distinct from classic scripts.
+fn inline_type_group(ty: &str) -> Option<&'static str> {
+    let group = match ty {
+        "html" | "svg" => "markup",
+        "css" => "css",
+        "js" => "script",
+        "mjs" | "module" => "module",
+        _ => return None,
+    };
+
+    Some(group)
+}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::Parser;
+
+    use super::{closest_name, inline_type_group, validate_target_path};
+    use crate::{
+        attr_state::{ElemAttrs, TrunkAttrState},
+        lsp::queries::attributes::trunk_elements,
+    };
+
+    /// Whether the outputs of the first two Trunk elements of the HTML collide.
+    fn outputs_collide(html: &str, filehash: bool) -> bool {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_html::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(html, None).unwrap();
+        let outputs = trunk_elements(tree.root_node(), html)
+            .into_iter()
+            .map(|elem| {
+                let mut cursor = elem.walk();
+                let attr_state =
+                    TrunkAttrState::from_elem_items(html, elem.named_children(&mut cursor))
+                        .unwrap();
+                attr_state
+                    .output(elem, &ElemAttrs::from_elem(html, elem), filehash)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        outputs[0].collides_with(&outputs[1])
+    }
+
+    #[test]
+    fn target_path_validation() {
+        assert!(validate_target_path("assets").is_ok());
+        assert!(validate_target_path("assets/img/").is_ok());
+        assert!(validate_target_path("./assets").is_ok());
+        assert_eq!(validate_target_path("").unwrap_err().0, "target-path-empty");
+        assert_eq!(
+            validate_target_path("  ").unwrap_err().0,
+            "target-path-empty"
+        );
+        assert_eq!(
+            validate_target_path("/assets").unwrap_err().0,
+            "target-path-absolute"
+        );
+        assert_eq!(
+            validate_target_path("\\assets").unwrap_err().0,
+            "target-path-absolute"
+        );
+        assert_eq!(
+            validate_target_path("C:\\assets").unwrap_err().0,
+            "target-path-drive"
+        );
+        assert_eq!(
+            validate_target_path("assets/../..").unwrap_err().0,
+            "target-path-parent"
+        );
+        assert_eq!(
+            validate_target_path("..\\assets").unwrap_err().0,
+            "target-path-parent"
+        );
+    }
+
+    #[test]
+    fn output_collisions() {
+        let css = r#"<link data-trunk rel="css" href="a/main.css">
+            <link data-trunk rel="css" href="b/main.css">"#;
+        assert!(!outputs_collide(css, true));
+        assert!(outputs_collide(css, false));
+
+        let copy = r#"<link data-trunk rel="copy-file" href="a/logo.png">
+            <link data-trunk rel="copy-file" href="b/logo.png">"#;
+        assert!(outputs_collide(copy, true));
+
+        let dir = r#"<link data-trunk rel="copy-dir" href="assets">
+            <link data-trunk rel="copy-file" href="logo.png" data-target-path="assets">"#;
+        assert!(outputs_collide(dir, true));
+    }
+
+    #[test]
+    fn inline_type_groups() {
+        assert_eq!(inline_type_group("mjs"), inline_type_group("module"));
+        assert_ne!(inline_type_group("js"), inline_type_group("module"));
+        assert_eq!(inline_type_group("svg"), inline_type_group("html"));
+        assert_eq!(inline_type_group("wasm"), None);
+    }
+
+    #[test]
+    fn typo_detection() {
+        let names = || ["data-cargo-features", "data-wasm-opt"].into_iter();
+        assert_eq!(
+            closest_name("data-cargo-feature", names()),
+            Some("data-cargo-features")
+        );
+        assert_eq!(closest_name("data-wasmopt", names()), Some("data-wasm-opt"));
+        assert_eq!(closest_name("data-bogus", names()), None);
+        assert_eq!(
+            closest_name("data_trunk", ["data-trunk"].into_iter()),
+            Some("data-trunk")
+        );
+        for attr in ["data-track", "data-turbo", "data-theme", "data-trunks-x"] {
+            assert_eq!(closest_name(attr, ["data-trunk"].into_iter()), None);
+        }
+    }
+}
@@ -8,6 +8,7 @@ mod rel_sass_scss;
 mod rel_tailwind;
 mod script;
 
+use crate::init::ClientCaps;
 use constcat::concat_slices;
 use lsp_types::{CompletionItem, Documentation, HoverContents, MarkupContent, MarkupKind};
 
@@ -59,6 +60,27 @@ const DATA_NO_MINIFY: (&str, &str, ValueRequirment) = (
     ValueRequirment::Requires(false),
 );
 
+/// The documentation of a completion item in a format the client renders.
+pub fn completion_docs(doc: &str) -> Documentation {
+    if ClientCaps::get().completion_markdown {
+        Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: doc.to_string(),
+        })
+    } else {
+        Documentation::String(doc.to_string())
+    }
+}
+
+/// Hover contents in a format the client renders.
+pub fn hover_markup(value: String) -> HoverContents {
+    let kind = match ClientCaps::get().hover_markdown {
+        true => MarkupKind::Markdown,
+        false => MarkupKind::PlainText,
+    };
+    HoverContents::Markup(MarkupContent { kind, value })
+}
+
 #[macro_export]
 macro_rules! load_md {
     ($struct:ident, $path:literal, $doc_of:literal) => {
@@ -90,10 +112,7 @@ macro_rules! completions {
                 pub fn completion() -> CompletionItem {
                     CompletionItem {
                         label: Self::DOC_OF.to_string(),
-                        documentation: Some(Documentation::MarkupContent(MarkupContent {
-                            kind: MarkupKind::Markdown,
-                            value: Self::as_str().to_string(),
-                        })),
+                        documentation: Some(completion_docs(Self::as_str())),
                         ..Default::default()
                     }
                 }
@@ -109,10 +128,7 @@ macro_rules! hover {
             impl $ident {
                 #[allow(unused)]
                 pub fn hover_contents() -> HoverContents {
-                    HoverContents::Markup(MarkupContent {
-                        kind: MarkupKind::Markdown,
-                        value: Self::as_str().to_string(),
-                    })
+                    hover_markup(Self::as_str().to_string())
                 }
             }
         )+
@@ -150,6 +166,15 @@ macro_rules! optional_asset_attrs {
     };
 }
 
+#[macro_export]
+macro_rules! asset_defaults {
+    ($ident:ident, $($arr:expr),*) => {
+        impl $ident {
+            pub const ASSET_DEFAULTS: &'static [(&str, &str)] = [$($arr),*].as_slice();
+        }
+    };
+}
+
 bulk_struct! {DataTrunk, RelCopyDir, RelCopyFile, RelCss, RelIcon, RelInline, RelRust, RelSass, RelScss, RelTailwind, Script}
 
 load_md!(DataTrunk, "data_trunk", "data-trunk");
@@ -247,11 +272,30 @@ optional_asset_attrs! {RelRust,
     ("data-typescript", rel_rust::DataTypeScript::as_str(), ValueRequirment::Allows),
     ("data-bindgen-target", rel_rust::DataBindgenTarget::as_str(), ValueRequirment::Requires(true)),
     ("data-loader-shim", rel_rust::DataLoaderShim::as_str(), ValueRequirment::Requires(false)),
-    ("data-cross-origin", rel_rust::DataCrossOrigin::as_str(), ValueRequirment::Requires(true))
+    ("data-cross-origin", rel_rust::DataCrossOrigin::as_str(), ValueRequirment::Requires(true)),
+    ("data-wasm-no-import", rel_rust::DataWasmNoImport::as_str(), ValueRequirment::Allows),
+    ("data-wasm-import-name", rel_rust::DataWasmImportName::as_str(), ValueRequirment::Requires(true))
 }
 
 required_asset_attrs! {Script, ("src", script::Src::as_str(), ValueRequirment::Requires(true))}
 optional_asset_attrs! {Script, DATA_NO_MINIFY, DATA_TARGET_PATH}
 
+const INTEGRITY_DEFAULT: (&str, &str) = ("data-integrity", "sha384");
+
+asset_defaults! {RelCopyFile, }
+asset_defaults! {RelCopyDir, }
+asset_defaults! {RelInline, }
+asset_defaults! {RelCss, INTEGRITY_DEFAULT}
+asset_defaults! {RelIcon, INTEGRITY_DEFAULT}
+asset_defaults! {RelTailwind, INTEGRITY_DEFAULT}
+asset_defaults! {RelSass, INTEGRITY_DEFAULT}
+asset_defaults! {RelScss, INTEGRITY_DEFAULT}
+asset_defaults! {RelRust,
+    ("data-type", "main"),
+    ("data-bindgen-target", "no-modules"),
+    ("data-cross-origin", "anonymous")
+}
+asset_defaults! {Script, }
+
 asset_attrs! {RelCopyDir, RelCopyFile, RelCss, RelIcon, RelInline, RelRust, RelSass, RelScss, RelTailwind, Script}
 hover! {DataTrunk, RelCopyDir, RelCopyFile, RelCss, RelIcon, RelInline, RelRust, RelSass, RelScss, RelTailwind, Script}
@@ -6,5 +6,5 @@ use tree_sitter::Tree;
 
 use texter::core::text::Text;
 
-type Documents = FxHashMap<Uri, (Tree, Text)>;
+pub type Documents = FxHashMap<Uri, (Tree, Text)>;
 pub static DOCUMENTS: LazyLock<Mutex<Documents>> = LazyLock::new(Mutex::default);
@@ -0,0 +1,252 @@
+use lsp_types::{FormattingOptions, Range, TextEdit};
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::{Attr, ElemAttrs, TrunkAttrState},
+    lsp::docs::ValueRequirment,
+    utils::node_range,
+};
+use texter::core::text::Text;
+
+use super::queries::attributes::trunk_elements;
+
+/// Formats the start tags of all Trunk elements that intersect with the provided range, or the
+/// whole document if there is none.
+///
+/// Attributes are put in their canonical order, which is `data-trunk`, `rel`, the required and
+/// optional attributes in schema order and lastly all other attributes in their original order.
+/// The rest of the document is left untouched.
+pub fn format(
+    range: Option<Range>,
+    options: &FormattingOptions,
+    n: Node,
+    text: &Text,
+) -> Vec<TextEdit> {
+    let s = text.text.as_str();
+    let mut edits = vec![];
+    for elem in trunk_elements(n, s) {
+        let Some(elem_range) = node_range(text, elem) else {
+            continue;
+        };
+        if range.is_some_and(|range| {
+            elem_range.end.line < range.start.line || elem_range.start.line > range.end.line
+        }) {
+            continue;
+        }
+        // Rendering a tag that failed to parse could drop the parts that are in error.
+        if elem.has_error() {
+            continue;
+        }
+
+        let mut cursor = elem.walk();
+        let Some(attr_state) = TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))
+        else {
+            continue;
+        };
+        let Some(formatted) = attr_state.format_elem(s, elem, options) else {
+            continue;
+        };
+        if formatted != s[elem.byte_range()] {
+            edits.push(TextEdit {
+                range: elem_range,
+                new_text: formatted,
+            });
+        }
+    }
+
+    edits
+}
+
+impl TrunkAttrState {
+    /// Renders the start tag with its attributes in canonical order.
+    ///
+    /// Tags that span multiple lines keep one attribute per line.
+    fn format_elem(&self, s: &str, elem: Node, options: &FormattingOptions) -> Option<String> {
+        let tag_name = elem
+            .named_child(0)
+            .filter(|n| n.kind() == "tag_name")?
+            .utf8_text(s.as_bytes())
+            .ok()?;
+        let attrs = ElemAttrs::from_elem(s, elem);
+        let schema = self.asset_attrs().unwrap_or_default();
+
+        let canonical: Vec<&str> = ["data-trunk", "rel"]
+            .into_iter()
+            .chain(schema.iter().map(|(name, _, _)| *name))
+            .collect();
+        let mut ordered: Vec<&Attr> = attrs.attrs.iter().collect();
+        // The sort is stable, so other attributes keep their original order at the end.
+        ordered.sort_by_key(|attr| {
+            canonical
+                .iter()
+                .position(|name| *name == attr.name)
+                .unwrap_or(usize::MAX)
+        });
+
+        let rendered = ordered.into_iter().map(|attr| {
+            let req = schema
+                .iter()
+                .find(|(name, _, _)| *name == attr.name)
+                .map(|(_, _, req)| req);
+            render_attr(attr, req)
+        });
+
+        let mut out = format!("<{tag_name}");
+        if elem.start_position().row == elem.end_position().row {
+            for attr in rendered {
+                out.push(' ');
+                out.push_str(&attr);
+            }
+            out.push_str(if elem.kind() == "self_closing_tag" {
+                " />"
+            } else {
+                ">"
+            });
+        } else {
+            let line_start = s[..elem.start_byte()].rfind('\n').map_or(0, |i| i + 1);
+            let indent: String = s[line_start..]
+                .chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect();
+            let unit = if options.insert_spaces {
+                " ".repeat(options.tab_size as usize)
+            } else {
+                "\t".to_string()
+            };
+            for attr in rendered {
+                out.push_str(&format!("\n{indent}{unit}{attr}"));
+            }
+            out.push_str(&format!("\n{indent}"));
+            out.push_str(if elem.kind() == "self_closing_tag" {
+                "/>"
+            } else {
+                ">"
+            });
+        }
+
+        Some(out)
+    }
+}
+
+/// Renders an attribute with a double quoted value, or without a value if it is a flag.
+fn render_attr(attr: &Attr, req: Option<&ValueRequirment>) -> String {
+    let value = match (req, attr.value) {
+        (Some(ValueRequirment::Requires(false)), _) => None,
+        (Some(ValueRequirment::Allows), Some("")) => None,
+        (_, value) => value,
+    };
+    match value {
+        None => attr.name.to_string(),
+        Some(value) if value.contains('"') => format!("{}='{value}'", attr.name),
+        Some(value) => format!("{}=\"{value}\"", attr.name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{FormattingOptions, Position};
+    use texter::core::text::Text;
+    use tree_sitter::Parser;
+
+    use super::format;
+
+    /// Formats the document and applies the edits, which only contain whole start tags.
+    fn formatted(html: &str, insert_spaces: bool) -> String {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_html::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(html, None).unwrap();
+        let text = Text::new(html.to_string());
+        let options = FormattingOptions {
+            tab_size: 2,
+            insert_spaces,
+            ..Default::default()
+        };
+
+        let mut out = html.to_string();
+        for edit in format(None, &options, tree.root_node(), &text)
+            .into_iter()
+            .rev()
+        {
+            let offset = |pos: Position| {
+                let line_start: usize = html
+                    .split_inclusive('\n')
+                    .take(pos.line as usize)
+                    .map(str::len)
+                    .sum();
+                line_start + pos.character as usize
+            };
+            out.replace_range(
+                offset(edit.range.start)..offset(edit.range.end),
+                &edit.new_text,
+            );
+        }
+
+        out
+    }
+
+    #[test]
+    fn attribute_order() {
+        assert_eq!(
+            formatted(
+                r#"<link href="a.css" data-custom rel="css" data-trunk data-integrity="none">"#,
+                true
+            ),
+            r#"<link data-trunk rel="css" href="a.css" data-integrity="none" data-custom>"#
+        );
+        assert_eq!(
+            formatted(r#"<script src="a.js" data-trunk defer></script>"#, true),
+            r#"<script data-trunk src="a.js" defer></script>"#
+        );
+    }
+
+    #[test]
+    fn quoting_and_flags() {
+        assert_eq!(
+            formatted(
+                r#"<link href=a.css data-no-minify="" rel='css' data-trunk title='say "hi"'>"#,
+                true
+            ),
+            r#"<link data-trunk rel="css" href="a.css" data-no-minify title='say "hi"'>"#
+        );
+        assert_eq!(
+            formatted(
+                r#"<link data-trunk rel="rust" data-wasm-opt="" data-loader-shim="yes" data-typescript="true">"#,
+                true
+            ),
+            r#"<link data-trunk rel="rust" data-wasm-opt data-typescript="true" data-loader-shim>"#
+        );
+    }
+
+    #[test]
+    fn multi_line_tags() {
+        let html = "<head>\n  <link href=\"a.css\"\n    rel=\"css\" data-trunk>\n</head>";
+        assert_eq!(
+            formatted(html, true),
+            "<head>\n  <link\n    data-trunk\n    rel=\"css\"\n    href=\"a.css\"\n  >\n</head>"
+        );
+        assert_eq!(
+            formatted(html, false),
+            "<head>\n  <link\n  \tdata-trunk\n  \trel=\"css\"\n  \thref=\"a.css\"\n  >\n</head>"
+        );
+    }
+
+    #[test]
+    fn self_closing_tags() {
+        assert_eq!(
+            formatted(r#"<link rel="css" href="a.css" data-trunk/>"#, true),
+            r#"<link data-trunk rel="css" href="a.css" />"#
+        );
+        assert_eq!(
+            formatted("<link rel=\"css\" data-trunk\n  href=\"a.css\" />", true),
+            "<link\n  data-trunk\n  rel=\"css\"\n  href=\"a.css\"\n/>"
+        );
+    }
+
+    #[test]
+    fn tags_with_errors_are_kept() {
+        let html = r#"<link href="a.css" data-trunk rel="css" ="x>"#;
+        assert_eq!(formatted(html, true), html);
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use lsp_types::{DocumentHighlight, DocumentHighlightKind, Uri};
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::{ElemAttrs, TrunkAttrState},
+    utils::{find_attr, find_elem, node_range, uri_to_path},
+};
+use texter::{change::GridIndex, core::text::Text};
+
+use super::queries::attributes::trunk_elements;
+
+/// The attributes whose values are highlighted.
+const HIGHLIGHT_ATTRS: &[&str] = &["href", "src", "data-bin"];
+
+/// Returns the Trunk elements of the document that reference the same target as the `href`,
+/// `src` or `data-bin` value at the position.
+pub fn document_highlights(
+    uri: &Uri,
+    pos: GridIndex,
+    n: Node,
+    text: &Text,
+) -> Option<Vec<DocumentHighlight>> {
+    let s = text.text.as_str();
+    let in_pos = n.named_descendant_for_point_range(pos.into(), pos.into())?;
+    let attr = find_attr(in_pos)?;
+    let elem = find_elem(attr)?;
+
+    let mut cursor = elem.walk();
+    let attr_state = TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))?;
+    if !attr_state.data_trunk {
+        return None;
+    }
+    let attrs = ElemAttrs::from_elem(s, elem);
+    let name = attrs.attrs.iter().find(|a| a.node == attr)?.name;
+    if !HIGHLIGHT_ATTRS.contains(&name) {
+        return None;
+    }
+
+    // Paths are compared as written if the document is not a file.
+    let dir = uri_to_path(uri)
+        .and_then(|html| Some(html.parent()?.to_path_buf()))
+        .unwrap_or_default();
+    let target = attr_state.highlight_target(&dir, &attrs, name)?;
+
+    let mut highlights = vec![];
+    for elem in trunk_elements(n, s) {
+        let mut cursor = elem.walk();
+        let Some(other_state) =
+            TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))
+        else {
+            continue;
+        };
+        let attrs = ElemAttrs::from_elem(s, elem);
+        if other_state.highlight_target(&dir, &attrs, name).as_ref() != Some(&target) {
+            continue;
+        }
+
+        // Rust assets without an `href` are highlighted on their tag name.
+        let Some(n) = attrs
+            .get(name)
+            .and_then(|attr| attr.value_node)
+            .or_else(|| elem.named_child(0))
+        else {
+            continue;
+        };
+        let Some(range) = node_range(text, n) else {
+            continue;
+        };
+        highlights.push(DocumentHighlight {
+            range,
+            kind: Some(DocumentHighlightKind::TEXT),
+        });
+    }
+
+    Some(highlights)
+}
+
+impl TrunkAttrState {
+    /// The source file of the asset, along with the binary if the attribute is `data-bin`.
+    fn highlight_target(
+        &self,
+        dir: &Path,
+        attrs: &ElemAttrs,
+        name: &str,
+    ) -> Option<(PathBuf, Option<String>)> {
+        let path = self.source_path(dir, attrs)?;
+        match name {
+            "data-bin" => Some((path, Some(attrs.value("data-bin")?.to_string()))),
+            _ => Some((path, None)),
+        }
+    }
+}
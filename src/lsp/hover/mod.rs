@@ -1,14 +1,14 @@
-use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Range};
+use lsp_types::{Hover, Range};
 use tracing::{instrument, trace};
 use tree_sitter::Node;
 
 use crate::{
-    attr_state::TrunkAttrState,
-    utils::{find_attr, find_elem},
+    attr_state::{ElemAttrs, TrunkAttrState},
+    utils::{find_attr, find_elem, node_range},
 };
 use texter::{change::GridIndex, core::text::Text};
 
-use super::docs::{DataTrunk, ValueRequirment};
+use super::docs::{hover_markup, DataTrunk, ValueRequirment};
 
 #[instrument(level = "trace")]
 pub fn hover(pos: GridIndex, n: Node, text: &Text) -> Option<Hover> {
@@ -21,12 +21,26 @@ pub fn hover(pos: GridIndex, n: Node, text: &Text) -> Option<Hover> {
         TrunkAttrState::from_elem_items(text.text.as_str(), elem.named_children(&mut cursor))?;
 
     match in_pos.kind() {
+        "tag_name" if attr_state.data_trunk => attr_state.hover_pipeline(text, elem, in_pos),
         "attribute_name" => attr_state.hover_attribute_name(text, in_pos),
         "attribute_value" => attr_state.hover_attribute_value(text, in_pos),
         _ => None,
     }
 }
 impl TrunkAttrState {
+    /// Accepts the element node and its "tag_name" node.
+    #[instrument(skip(text), level = "trace")]
+    fn hover_pipeline(&self, text: &Text, elem: Node, in_pos: Node) -> Option<Hover> {
+        assert_eq!(in_pos.kind(), "tag_name");
+        let attrs = ElemAttrs::from_elem(text.text.as_str(), elem);
+        let pipeline = self.pipeline(&attrs)?;
+
+        Some(Hover {
+            contents: hover_markup(pipeline),
+            range: node_range(text, in_pos),
+        })
+    }
+
     #[instrument(skip(text), level = "trace")]
     fn hover_attribute_name(&self, text: &Text, in_pos: Node) -> Option<Hover> {
         assert_eq!(in_pos.kind(), "attribute_name");
@@ -54,14 +68,11 @@ impl TrunkAttrState {
 
         trace!("Found asset specific hover");
         let mut start_pos = GridIndex::from(in_pos.start_position());
-        start_pos.denormalize(text).unwrap();
+        start_pos.denormalize(text).ok()?;
         let mut end_pos = GridIndex::from(in_pos.end_position());
-        end_pos.denormalize(text).unwrap();
+        end_pos.denormalize(text).ok()?;
         Some(Hover {
-            contents: HoverContents::Markup(MarkupContent {
-                kind: MarkupKind::Markdown,
-                value: hover.1.to_string(),
-            }),
+            contents: hover_markup(hover.1.to_string()),
             range: Some(Range {
                 start: start_pos.into(),
                 end: end_pos.into(),
@@ -91,15 +102,12 @@ impl TrunkAttrState {
         };
 
         let mut start_pos = GridIndex::from(in_pos.start_position());
-        start_pos.denormalize(text).unwrap();
+        start_pos.denormalize(text).ok()?;
         let mut end_pos = GridIndex::from(in_pos.end_position());
-        end_pos.denormalize(text).unwrap();
+        end_pos.denormalize(text).ok()?;
 
         Some(Hover {
-            contents: HoverContents::Markup(MarkupContent {
-                kind: MarkupKind::Markdown,
-                value: val_doc.to_string(),
-            }),
+            contents: hover_markup(val_doc.to_string()),
             range: Some(Range {
                 start: start_pos.into(),
                 end: end_pos.into(),
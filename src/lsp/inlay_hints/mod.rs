@@ -0,0 +1,116 @@
+use lsp_types::{InlayHint, InlayHintLabel, InlayHintTooltip, Range};
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::{AssetType, ElemAttrs, TrunkAttrState},
+    utils::node_range,
+};
+use texter::core::text::Text;
+
+use super::queries::attributes::trunk_elements;
+
+/// Returns the inlay hints for all Trunk elements that intersect with the provided range.
+pub fn inlay_hints(range: Range, n: Node, text: &Text) -> Vec<InlayHint> {
+    let s = text.text.as_str();
+    let mut hints = vec![];
+    for elem in trunk_elements(n, s) {
+        let Some(elem_range) = node_range(text, elem) else {
+            continue;
+        };
+        if elem_range.end.line < range.start.line || elem_range.start.line > range.end.line {
+            continue;
+        }
+
+        let mut cursor = elem.walk();
+        let Some(attr_state) = TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))
+        else {
+            continue;
+        };
+        let attrs = ElemAttrs::from_elem(s, elem);
+        attr_state.push_inlay_hints(text, elem, &attrs, &mut hints);
+    }
+
+    hints
+}
+
+impl TrunkAttrState {
+    /// Adds the implicit default values of omitted attributes and the resolved output path of
+    /// `data-target-path`.
+    fn push_inlay_hints(
+        &self,
+        text: &Text,
+        elem: Node,
+        attrs: &ElemAttrs,
+        hints: &mut Vec<InlayHint>,
+    ) {
+        // Defaults are shown after the last attribute, or the tag name if there are none.
+        let mut cursor = elem.walk();
+        let Some(last) = elem
+            .named_children(&mut cursor)
+            .filter(|n| matches!(n.kind(), "tag_name" | "attribute"))
+            .last()
+        else {
+            return;
+        };
+        let Some(end) = node_range(text, last).map(|range| range.end) else {
+            return;
+        };
+
+        let target = attrs
+            .get("data-target-path")
+            .and_then(|target| node_range(text, target.node));
+        if let Some((target, mut out)) = target.zip(self.output_path(attrs)) {
+            if self.rel == Some(AssetType::CopyDir) {
+                out.push('/');
+            }
+            hints.push(InlayHint {
+                position: target.end,
+                label: InlayHintLabel::String(format!("→ dist/{out}")),
+                kind: None,
+                text_edits: None,
+                tooltip: Some(InlayHintTooltip::String(
+                    "The path of the output inside the dist directory.".to_string(),
+                )),
+                padding_left: Some(true),
+                padding_right: None,
+                data: None,
+            });
+        }
+
+        if self.rel == Some(AssetType::Inline) && !attrs.has("type") {
+            if let Some(ty) = self.inline_type(attrs) {
+                hints.push(InlayHint {
+                    position: end,
+                    label: InlayHintLabel::String(format!("type=\"{ty}\"")),
+                    kind: None,
+                    text_edits: None,
+                    tooltip: Some(InlayHintTooltip::String(
+                        "The type is inferred from the file extension.".to_string(),
+                    )),
+                    padding_left: Some(true),
+                    padding_right: None,
+                    data: None,
+                });
+            }
+        }
+
+        for (attr_name, default) in self.asset_defaults() {
+            if attrs.has(attr_name) {
+                continue;
+            }
+
+            hints.push(InlayHint {
+                position: end,
+                label: InlayHintLabel::String(format!("{attr_name}=\"{default}\"")),
+                kind: None,
+                text_edits: None,
+                tooltip: Some(InlayHintTooltip::String(format!(
+                    "`{attr_name}` defaults to `{default}` when omitted."
+                ))),
+                padding_left: Some(true),
+                padding_right: None,
+                data: None,
+            });
+        }
+    }
+}
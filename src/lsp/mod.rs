@@ -1,37 +1,88 @@
+mod cargo_toml;
+mod code_actions;
+pub mod code_lens;
 mod completions;
+mod diagnostics;
 pub mod docs;
 mod document;
+mod formatting;
+mod highlight;
 mod hover;
+mod inlay_hints;
+mod pipeline;
+pub mod preview;
 pub mod queries;
+mod references;
+mod rename;
+pub mod semantic_tokens;
+mod symbols;
+mod trunk_toml;
+pub mod workspace;
+
+use std::path::PathBuf;
 
 use anyhow::Context;
+use code_actions::code_actions;
+use code_lens::{code_lenses, open_file, resolve_code_lens, OPEN_COMMAND};
 use completions::completions;
+use diagnostics::diagnostics;
 use document::DOCUMENTS;
+use formatting::format;
+use highlight::document_highlights;
 use hover::hover;
-use lsp_server::{Connection, Message, Response};
+use inlay_hints::inlay_hints;
+use lsp_server::{Connection, ErrorCode, Message, Response};
 use lsp_types::{
     notification::{
-        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification,
+        DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument, DidOpenTextDocument,
+        Notification, PublishDiagnostics,
+    },
+    request::{
+        CodeActionRequest, CodeLensRequest, CodeLensResolve, Completion, DocumentHighlightRequest,
+        DocumentSymbolRequest, ExecuteCommand, Formatting, HoverRequest, InlayHintRequest,
+        PrepareRenameRequest, RangeFormatting, References, Rename, Request,
+        SemanticTokensFullRequest, SemanticTokensRangeRequest, WillRenameFiles,
+        WorkspaceSymbolRequest,
     },
-    request::{Completion, HoverRequest, Request},
-    CompletionParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
-    DidOpenTextDocumentParams, HoverParams, TextDocumentPositionParams, Uri,
+    CodeActionParams, CodeLens, CodeLensParams, CompletionParams, Diagnostic,
+    DidChangeTextDocumentParams, DidChangeWatchedFilesParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentHighlightParams,
+    DocumentRangeFormattingParams, DocumentSymbolParams, DocumentSymbolResponse,
+    ExecuteCommandParams, HoverParams, InlayHintParams, PublishDiagnosticsParams, ReferenceParams,
+    RenameFilesParams, RenameParams, SemanticTokensParams, SemanticTokensRangeParams,
+    TextDocumentPositionParams, Uri, WorkspaceSymbolParams, WorkspaceSymbolResponse,
 };
+use preview::{preview, PREVIEW_COMMAND};
+use references::{referenced_path, references};
+use rename::{prepare_rename, rename, will_rename_files};
+use semantic_tokens::semantic_tokens;
+use symbols::document_symbols;
 use tracing::warn;
 use tree_sitter::Parser;
+use trunk_toml::dist_dir;
+use workspace::{update_index, workspace_symbols};
 
-use crate::init::TextFn;
+use crate::{
+    init::{ClientCaps, TextFn},
+    utils::uri_to_path,
+};
 use texter::change::{Change, GridIndex};
 
-pub fn main_loop(text_fn: TextFn, con: Connection) -> anyhow::Result<()> {
+pub fn main_loop(text_fn: TextFn, roots: Vec<PathBuf>, con: Connection) -> anyhow::Result<()> {
     let mut parser = Parser::new();
     parser.set_language(&tree_sitter_html::LANGUAGE.into())?;
-    for msg in con.receiver {
+    for msg in &con.receiver {
         match msg {
-            Message::Notification(noti) => handle_notification(&mut parser, text_fn, noti)?,
-            Message::Request(req) => con
-                .sender
-                .send(Message::Response(handle_request(&mut parser, req)?))?,
+            Message::Notification(noti) => {
+                handle_notification(&con, &mut parser, text_fn, &roots, noti)?
+            }
+            Message::Request(req) => con.sender.send(Message::Response(handle_request(
+                &con,
+                &mut parser,
+                text_fn,
+                &roots,
+                req,
+            )?))?,
             _ => continue,
         };
     }
@@ -40,8 +91,10 @@ pub fn main_loop(text_fn: TextFn, con: Connection) -> anyhow::Result<()> {
 }
 
 fn handle_notification(
+    con: &Connection,
     parser: &mut Parser,
     text_fn: TextFn,
+    roots: &[PathBuf],
     noti: lsp_server::Notification,
 ) -> anyhow::Result<()> {
     let mut docs = DOCUMENTS.lock().unwrap();
@@ -52,19 +105,30 @@ fn handle_notification(
             for ch in p.content_changes.into_iter() {
                 text.update(Change::from(ch), tree)?;
             }
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            let diags = diagnostics(&p.text_document.uri, tree.root_node(), text);
+            publish_diagnostics(con, p.text_document.uri, diags)?;
         }
         DidOpenTextDocument::METHOD => {
             let p: DidOpenTextDocumentParams = serde_json::from_value(noti.params)?;
             let tree = parser
                 .parse(p.text_document.text.as_bytes(), None)
                 .context("Tree not returned during parsing")?;
-            docs.insert(p.text_document.uri, (tree, text_fn(p.text_document.text)));
+            let text = text_fn(p.text_document.text);
+            let diags = diagnostics(&p.text_document.uri, tree.root_node(), &text);
+            docs.insert(p.text_document.uri.clone(), (tree, text));
+            publish_diagnostics(con, p.text_document.uri, diags)?;
         }
         DidCloseTextDocument::METHOD => {
             let p: DidCloseTextDocumentParams = serde_json::from_value(noti.params)?;
             if docs.remove(&p.text_document.uri).is_none() {
                 warn!("Closed non registered document.")
             }
+            publish_diagnostics(con, p.text_document.uri, vec![])?;
+        }
+        DidChangeWatchedFiles::METHOD => {
+            let p: DidChangeWatchedFilesParams = serde_json::from_value(noti.params)?;
+            update_index(parser, text_fn, roots, p);
         }
         method => warn!("Unsupported notification recieved -> {}", method),
     };
@@ -72,7 +136,32 @@ fn handle_notification(
     Ok(())
 }
 
-fn handle_request(parser: &mut Parser, req: lsp_server::Request) -> anyhow::Result<Response> {
+fn publish_diagnostics(
+    con: &Connection,
+    uri: Uri,
+    diagnostics: Vec<Diagnostic>,
+) -> anyhow::Result<()> {
+    let params = PublishDiagnosticsParams {
+        uri,
+        diagnostics,
+        version: None,
+    };
+    con.sender
+        .send(Message::Notification(lsp_server::Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            params,
+        )))?;
+
+    Ok(())
+}
+
+fn handle_request(
+    con: &Connection,
+    parser: &mut Parser,
+    text_fn: TextFn,
+    roots: &[PathBuf],
+    req: lsp_server::Request,
+) -> anyhow::Result<Response> {
     let mut docs = DOCUMENTS.lock().unwrap();
     match req.method.as_str() {
         Completion::METHOD => {
@@ -94,9 +183,10 @@ fn handle_request(parser: &mut Parser, req: lsp_server::Request) -> anyhow::Resu
                 .context("Requested completion for unknown document.")?;
             *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
             pos.normalize(text)?;
+            let dist = uri_to_path(&uri).and_then(|path| dist_dir(&path));
             return Ok(Response::new_ok(
                 req.id,
-                completions(pos, tree.root_node(), text),
+                completions(pos, tree.root_node(), text, dist.as_deref()),
             ));
         }
         HoverRequest::METHOD => {
@@ -113,6 +203,230 @@ fn handle_request(parser: &mut Parser, req: lsp_server::Request) -> anyhow::Resu
             pos.normalize(text)?;
             return Ok(Response::new_ok(req.id, hover(pos, tree.root_node(), text)));
         }
+        CodeActionRequest::METHOD => {
+            let p: CodeActionParams = serde_json::from_value(req.params)?;
+            let (tree, text) = docs
+                .get_mut(&p.text_document.uri)
+                .context("Requested code actions for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            return Ok(Response::new_ok(
+                req.id,
+                code_actions(&p.text_document.uri, p.range, tree.root_node(), text),
+            ));
+        }
+        InlayHintRequest::METHOD => {
+            let p: InlayHintParams = serde_json::from_value(req.params)?;
+            let (tree, text) = docs
+                .get_mut(&p.text_document.uri)
+                .context("Requested inlay hints for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            return Ok(Response::new_ok(
+                req.id,
+                inlay_hints(p.range, tree.root_node(), text),
+            ));
+        }
+        DocumentSymbolRequest::METHOD => {
+            let p: DocumentSymbolParams = serde_json::from_value(req.params)?;
+            let (tree, text) = docs
+                .get_mut(&p.text_document.uri)
+                .context("Requested document symbols for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            return Ok(Response::new_ok(
+                req.id,
+                DocumentSymbolResponse::Nested(document_symbols(tree.root_node(), text)),
+            ));
+        }
+        DocumentHighlightRequest::METHOD => {
+            let p: DocumentHighlightParams = serde_json::from_value(req.params)?;
+            let TextDocumentPositionParams {
+                text_document: id,
+                position: pos,
+            } = p.text_document_position_params;
+            let (tree, text) = docs
+                .get_mut(&id.uri)
+                .context("Requested document highlights for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            let mut pos = GridIndex::from(pos);
+            pos.normalize(text)?;
+            return Ok(Response::new_ok(
+                req.id,
+                document_highlights(&id.uri, pos, tree.root_node(), text),
+            ));
+        }
+        SemanticTokensFullRequest::METHOD => {
+            let p: SemanticTokensParams = serde_json::from_value(req.params)?;
+            let (tree, text) = docs
+                .get_mut(&p.text_document.uri)
+                .context("Requested semantic tokens for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            return Ok(Response::new_ok(
+                req.id,
+                semantic_tokens(None, tree.root_node(), text),
+            ));
+        }
+        SemanticTokensRangeRequest::METHOD => {
+            let p: SemanticTokensRangeParams = serde_json::from_value(req.params)?;
+            let (tree, text) = docs
+                .get_mut(&p.text_document.uri)
+                .context("Requested semantic tokens for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            return Ok(Response::new_ok(
+                req.id,
+                semantic_tokens(Some(p.range), tree.root_node(), text),
+            ));
+        }
+        References::METHOD => {
+            let p: ReferenceParams = serde_json::from_value(req.params)?;
+            let TextDocumentPositionParams {
+                text_document: id,
+                position: pos,
+            } = p.text_document_position;
+            // Other files, such as stylesheets, are looked up by their own path. Clients may
+            // have opened them too, so the extension decides instead of the open documents.
+            let path = uri_to_path(&id.uri);
+            let is_html = path
+                .as_ref()
+                .and_then(|path| path.extension())
+                .is_some_and(|ext| ext == "html" || ext == "htm");
+            let target = match docs.get_mut(&id.uri).filter(|_| is_html) {
+                Some((tree, text)) => {
+                    *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+                    let mut pos = GridIndex::from(pos);
+                    pos.normalize(text)?;
+                    referenced_path(&id.uri, pos, tree.root_node(), text)
+                }
+                None => path,
+            };
+            return Ok(Response::new_ok(
+                req.id,
+                target.map(|target| references(&target, &docs)),
+            ));
+        }
+        PrepareRenameRequest::METHOD => {
+            let p: TextDocumentPositionParams = serde_json::from_value(req.params)?;
+            let (tree, text) = docs
+                .get_mut(&p.text_document.uri)
+                .context("Requested rename for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            let mut pos = GridIndex::from(p.position);
+            pos.normalize(text)?;
+            return Ok(
+                match prepare_rename(&p.text_document.uri, pos, tree.root_node(), text) {
+                    Ok(range) => Response::new_ok(req.id, range),
+                    Err(err) => Response::new_err(req.id, ErrorCode::RequestFailed as i32, err),
+                },
+            );
+        }
+        Rename::METHOD => {
+            let p: RenameParams = serde_json::from_value(req.params)?;
+            let TextDocumentPositionParams {
+                text_document: id,
+                position: pos,
+            } = p.text_document_position;
+            let (tree, text) = docs
+                .get_mut(&id.uri)
+                .context("Requested rename for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            let mut pos = GridIndex::from(pos);
+            pos.normalize(text)?;
+            return Ok(match rename(&id.uri, pos, &p.new_name, text_fn, &docs) {
+                Ok(edit) => Response::new_ok(req.id, edit),
+                Err(err) => Response::new_err(req.id, ErrorCode::RequestFailed as i32, err),
+            });
+        }
+        WillRenameFiles::METHOD => {
+            let p: RenameFilesParams = serde_json::from_value(req.params)?;
+            return Ok(Response::new_ok(req.id, will_rename_files(p, &docs)));
+        }
+        WorkspaceSymbolRequest::METHOD => {
+            let p: WorkspaceSymbolParams = serde_json::from_value(req.params)?;
+            let symbols = workspace_symbols(&p.query, roots, &docs);
+            return Ok(Response::new_ok(
+                req.id,
+                WorkspaceSymbolResponse::Nested(symbols),
+            ));
+        }
+        Formatting::METHOD => {
+            let p: DocumentFormattingParams = serde_json::from_value(req.params)?;
+            let (tree, text) = docs
+                .get_mut(&p.text_document.uri)
+                .context("Requested formatting for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            return Ok(Response::new_ok(
+                req.id,
+                format(None, &p.options, tree.root_node(), text),
+            ));
+        }
+        RangeFormatting::METHOD => {
+            let p: DocumentRangeFormattingParams = serde_json::from_value(req.params)?;
+            let (tree, text) = docs
+                .get_mut(&p.text_document.uri)
+                .context("Requested range formatting for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            return Ok(Response::new_ok(
+                req.id,
+                format(Some(p.range), &p.options, tree.root_node(), text),
+            ));
+        }
+        CodeLensRequest::METHOD => {
+            let p: CodeLensParams = serde_json::from_value(req.params)?;
+            let (tree, text) = docs
+                .get_mut(&p.text_document.uri)
+                .context("Requested code lenses for unknown document.")?;
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            return Ok(Response::new_ok(
+                req.id,
+                code_lenses(&p.text_document.uri, tree.root_node(), text),
+            ));
+        }
+        CodeLensResolve::METHOD => {
+            let lens: CodeLens = serde_json::from_value(req.params)?;
+            return Ok(Response::new_ok(req.id, resolve_code_lens(lens)));
+        }
+        ExecuteCommand::METHOD => {
+            // Invalid commands are reported to the client instead of stopping the server.
+            let invalid_params = |id, message: &str| {
+                Response::new_err(id, ErrorCode::InvalidParams as i32, message.to_string())
+            };
+            let p: ExecuteCommandParams = match serde_json::from_value(req.params) {
+                Ok(p) => p,
+                Err(err) => return Ok(invalid_params(req.id, &err.to_string())),
+            };
+            match p.command.as_str() {
+                PREVIEW_COMMAND | OPEN_COMMAND => {}
+                command => {
+                    warn!("Unsupported command recieved -> {}", command);
+                    return Ok(Response::new_ok(req.id, None::<String>));
+                }
+            };
+            let Some(uri) = p
+                .arguments
+                .into_iter()
+                .next()
+                .and_then(|arg| serde_json::from_value::<Uri>(arg).ok())
+            else {
+                return Ok(invalid_params(req.id, "Command requires a URI argument."));
+            };
+            if p.command == OPEN_COMMAND {
+                if !ClientCaps::get().show_document {
+                    return Ok(invalid_params(req.id, "The client cannot show documents."));
+                }
+                open_file(con, uri)?;
+                return Ok(Response::new_ok(req.id, None::<String>));
+            }
+
+            let Some((tree, text)) = docs.get_mut(&uri) else {
+                return Ok(invalid_params(
+                    req.id,
+                    "Requested preview for unknown document.",
+                ));
+            };
+            *tree = parser.parse(text.text.as_str(), Some(tree)).unwrap();
+            return Ok(Response::new_ok(
+                req.id,
+                preview(tree.root_node(), text.text.as_str()),
+            ));
+        }
         _ => {}
     }
 
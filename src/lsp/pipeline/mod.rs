@@ -0,0 +1,360 @@
+use std::path::Path;
+
+use crate::attr_state::{AssetType, ElemAttrs, TagName, TrunkAttrState};
+
+/// Placeholder for the content hash Trunk adds to the names of hashed outputs.
+pub const HASH: &str = "{hash}";
+
+impl TrunkAttrState {
+    /// Returns the value of an attribute, falling back to the documented default.
+    pub fn value_or_default<'a>(&self, attrs: &ElemAttrs<'a>, name: &str) -> Option<&'a str> {
+        attrs.value(name).or_else(|| {
+            self.asset_defaults()
+                .iter()
+                .find(|(attr_name, _)| *attr_name == name)
+                .map(|(_, val)| *val)
+        })
+    }
+
+    /// The type used to inline a `rel="inline"` asset.
+    ///
+    /// If the `type` attribute is not present the type is inferred from the `href` extension.
+    pub fn inline_type<'a>(&self, attrs: &ElemAttrs<'a>) -> Option<&'a str> {
+        attrs
+            .value("type")
+            .or_else(|| infer_inline_type(attrs.value("href")?))
+    }
+
+    /// The file name of the main output of the asset inside the dist directory.
+    ///
+    /// Returns `None` for assets that are inlined into the HTML.
+    pub fn output_name(&self, attrs: &ElemAttrs) -> Option<String> {
+        let href = match self.tag_name {
+            TagName::Script => attrs.value("src")?,
+            TagName::Link => attrs.value("href").unwrap_or_default(),
+            TagName::Unknown => return None,
+        };
+        let path = Path::new(href);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(href);
+        let name = path.file_name().and_then(|s| s.to_str()).unwrap_or(href);
+
+        let out = match (self.tag_name, self.rel) {
+            (TagName::Script, _) => format!("{stem}-{HASH}.js"),
+            (_, Some(AssetType::Rust)) => {
+                let target = attrs
+                    .value("data-bin")
+                    .or_else(|| attrs.value("data-target-name"))
+                    .unwrap_or("{crate}");
+                format!("{target}-{HASH}.js")
+            }
+            (_, Some(AssetType::Css)) => format!("{stem}-{HASH}.css"),
+            (_, Some(AssetType::Sass | AssetType::Scss | AssetType::Tailwind)) => {
+                if attrs.has("data-inline") {
+                    return None;
+                }
+                format!("{stem}-{HASH}.css")
+            }
+            (_, Some(AssetType::Icon)) => match path.extension().and_then(|ext| ext.to_str()) {
+                Some(ext) => format!("{stem}-{HASH}.{ext}"),
+                None => format!("{stem}-{HASH}"),
+            },
+            (_, Some(AssetType::CopyFile | AssetType::CopyDir)) => name.to_string(),
+            (_, Some(AssetType::Inline) | None) => return None,
+        };
+
+        Some(out)
+    }
+
+    /// The path of the main output of the asset, relative to the dist directory.
+    pub fn output_path(&self, attrs: &ElemAttrs) -> Option<String> {
+        let name = self.output_name(attrs)?;
+        Some(target_join(attrs.value("data-target-path"), &name))
+    }
+
+    /// Describes the steps Trunk takes to process the asset as markdown.
+    pub fn pipeline(&self, attrs: &ElemAttrs) -> Option<String> {
+        let mut steps = match (self.tag_name, self.rel) {
+            (TagName::Script, _) => self.script_pipeline(attrs),
+            (TagName::Link, Some(AssetType::Rust)) => self.rust_pipeline(attrs),
+            (TagName::Link, Some(AssetType::Css | AssetType::Icon)) => self.copy_pipeline(attrs),
+            (TagName::Link, Some(AssetType::Sass | AssetType::Scss)) => self.sass_pipeline(attrs),
+            (TagName::Link, Some(AssetType::Tailwind)) => self.tailwind_pipeline(attrs),
+            (TagName::Link, Some(AssetType::Inline)) => self.inline_pipeline(attrs),
+            (TagName::Link, Some(AssetType::CopyFile)) => vec![format!(
+                "Copies `{}` as is to `dist/{}`.",
+                attrs.value("href").unwrap_or_default(),
+                self.output_path(attrs)?
+            )],
+            (TagName::Link, Some(AssetType::CopyDir)) => vec![format!(
+                "Recursively copies `{}` as is to `dist/{}/`.",
+                attrs.value("href").unwrap_or_default(),
+                self.output_path(attrs)?
+            )],
+            _ => return None,
+        };
+
+        if let Some(integrity) = self.value_or_default(attrs, "data-integrity") {
+            if self
+                .asset_attrs()?
+                .iter()
+                .any(|(a, _, _)| *a == "data-integrity")
+            {
+                steps.push(match integrity {
+                    "none" => "No integrity digest is added.".to_string(),
+                    alg => format!("Adds an `integrity` digest using `{alg}`."),
+                });
+            }
+        }
+
+        let mut md = String::from("**Trunk pipeline**\n");
+        for step in steps {
+            md.push_str("\n- ");
+            md.push_str(&step);
+        }
+        Some(md)
+    }
+
+    fn rust_pipeline(&self, attrs: &ElemAttrs) -> Vec<String> {
+        let mut cargo = String::from("cargo build --target=wasm32-unknown-unknown");
+        // Trunk accepts the directory of the crate, cargo requires the manifest itself.
+        let manifest = match attrs.value("href").filter(|href| !href.is_empty()) {
+            Some(href) if href.ends_with(".toml") => href.to_string(),
+            Some(dir) => format!("{}/Cargo.toml", dir.trim_end_matches(['/', '\\'])),
+            None => "Cargo.toml".to_string(),
+        };
+        cargo.push_str(" --manifest-path=");
+        cargo.push_str(&manifest);
+        if let Some(bin) = attrs.value("data-bin") {
+            cargo.push_str(" --bin=");
+            cargo.push_str(bin);
+        }
+        if attrs.has("data-cargo-no-default-features") {
+            cargo.push_str(" --no-default-features");
+        }
+        if attrs.has("data-cargo-all-features") {
+            cargo.push_str(" --all-features");
+        }
+        if let Some(features) = attrs.value("data-cargo-features") {
+            let features: Vec<&str> = features
+                .split([' ', ','])
+                .filter(|f| !f.is_empty())
+                .collect();
+            cargo.push_str(" --features=");
+            cargo.push_str(&features.join(","));
+        }
+
+        let mut bindgen = String::from("wasm-bindgen --target=");
+        bindgen.push_str(
+            self.value_or_default(attrs, "data-bindgen-target")
+                .unwrap_or_default(),
+        );
+        for (attr, flag) in [
+            ("data-keep-debug", " --keep-debug"),
+            ("data-no-demangle", " --no-demangle"),
+            ("data-reference-types", " --reference-types"),
+            ("data-weak-refs", " --weak-refs"),
+        ] {
+            if attrs.has(attr) {
+                bindgen.push_str(flag);
+            }
+        }
+        bindgen.push_str(if attrs.has("data-typescript") {
+            " --typescript"
+        } else {
+            " --no-typescript"
+        });
+
+        let wasm_opt = match attrs
+            .get("data-wasm-opt")
+            .map(|a| a.value.unwrap_or_default())
+        {
+            Some("0") => "`wasm-opt` is disabled.".to_string(),
+            Some("") | None => {
+                "`wasm-opt` at its default level (only with `--release`).".to_string()
+            }
+            Some(level) => {
+                let mut cmd = format!("`wasm-opt -O{level}");
+                if let Some(params) = attrs.value("data-wasm-opt-params") {
+                    cmd.push(' ');
+                    cmd.push_str(params);
+                }
+                cmd.push_str("` (only with `--release`)");
+                cmd
+            }
+        };
+
+        let load = match self.value_or_default(attrs, "data-type") {
+            Some("worker") if attrs.has("data-loader-shim") => {
+                "Loaded as a web worker through a generated loader shim."
+            }
+            Some("worker") => "Loaded as a web worker.",
+            _ => "Loaded as the main application.",
+        };
+
+        vec![
+            format!("`{cargo}` (`--release` with `trunk build --release`)"),
+            format!("`{bindgen}`"),
+            wasm_opt,
+            format!(
+                "{load} Resources use `crossorigin=\"{}\"`.",
+                self.value_or_default(attrs, "data-cross-origin")
+                    .unwrap_or_default()
+            ),
+        ]
+    }
+
+    fn copy_pipeline(&self, attrs: &ElemAttrs) -> Vec<String> {
+        let mut steps = vec![format!(
+            "Copies `{}` to `dist/{}`.",
+            attrs.value("href").unwrap_or_default(),
+            self.output_path(attrs).unwrap_or_default()
+        )];
+        steps.push(minify_step(attrs));
+        steps
+    }
+
+    fn sass_pipeline(&self, attrs: &ElemAttrs) -> Vec<String> {
+        let href = attrs.value("href").unwrap_or_default();
+        vec![
+            format!("`sass --no-source-map --style=expanded {href}` (`--style=compressed` with `--release`)"),
+            self.css_output_step(attrs),
+        ]
+    }
+
+    fn tailwind_pipeline(&self, attrs: &ElemAttrs) -> Vec<String> {
+        let href = attrs.value("href").unwrap_or_default();
+        let minify = if attrs.has("data-no-minify") {
+            ""
+        } else {
+            " (`--minify` with `--release`)"
+        };
+        vec![
+            format!("`tailwindcss --input {href}`{minify}"),
+            self.css_output_step(attrs),
+        ]
+    }
+
+    fn css_output_step(&self, attrs: &ElemAttrs) -> String {
+        match self.output_path(attrs) {
+            Some(out) => {
+                format!(
+                    "Writes the CSS to `dist/{out}` and links it with `<link rel=\"stylesheet\">`."
+                )
+            }
+            None => "Inlines the CSS into a `<style>` tag.".to_string(),
+        }
+    }
+
+    fn inline_pipeline(&self, attrs: &ElemAttrs) -> Vec<String> {
+        let href = attrs.value("href").unwrap_or_default();
+        let ty = attrs
+            .value("type")
+            .unwrap_or("inferred from the file extension");
+        vec![format!(
+            "Inlines the content of `{href}` into the HTML as `{ty}`, no hashing is performed."
+        )]
+    }
+
+    fn script_pipeline(&self, attrs: &ElemAttrs) -> Vec<String> {
+        vec![
+            format!(
+                "Copies `{}` to `dist/{}`.",
+                attrs.value("src").unwrap_or_default(),
+                self.output_path(attrs).unwrap_or_default()
+            ),
+            minify_step(attrs),
+        ]
+    }
+}
+
+fn minify_step(attrs: &ElemAttrs) -> String {
+    if attrs.has("data-no-minify") {
+        "Minification is disabled.".to_string()
+    } else {
+        "Minified with `--release`.".to_string()
+    }
+}
+
+/// Infers the type of a `rel="inline"` asset the same way Trunk does, by its file extension.
+pub fn infer_inline_type(href: &str) -> Option<&'static str> {
+    let ext = Path::new(href).extension()?.to_str()?;
+    let ty = match ext {
+        "html" => "html",
+        "svg" => "svg",
+        "css" => "css",
+        "js" => "js",
+        "mjs" => "mjs",
+        _ => return None,
+    };
+
+    Some(ty)
+}
+
+/// Joins a file name to a `data-target-path` value.
+pub fn target_join(target_path: Option<&str>, name: &str) -> String {
+    let mut path = String::new();
+    if let Some(target) = target_path
+        .map(|t| t.trim_matches('/'))
+        .filter(|t| !t.is_empty())
+    {
+        path.push_str(target);
+        path.push('/');
+    }
+    path.push_str(name);
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::Parser;
+
+    use super::infer_inline_type;
+    use crate::{
+        attr_state::{ElemAttrs, TrunkAttrState},
+        lsp::queries::attributes::trunk_elements,
+    };
+
+    /// The pipeline of the first Trunk element of the HTML.
+    fn pipeline_of(html: &str) -> String {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_html::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(html, None).unwrap();
+        let elem = trunk_elements(tree.root_node(), html)[0];
+        let mut cursor = elem.walk();
+        let attr_state =
+            TrunkAttrState::from_elem_items(html, elem.named_children(&mut cursor)).unwrap();
+        attr_state
+            .pipeline(&ElemAttrs::from_elem(html, elem))
+            .unwrap()
+    }
+
+    #[test]
+    fn rust_manifest_path() {
+        let pipeline = pipeline_of(r#"<link data-trunk rel="rust" href="crates/app/">"#);
+        assert!(pipeline.contains("--manifest-path=crates/app/Cargo.toml"));
+        let pipeline = pipeline_of(r#"<link data-trunk rel="rust" href="app/Cargo.toml">"#);
+        assert!(pipeline.contains("--manifest-path=app/Cargo.toml"));
+        let pipeline = pipeline_of(r#"<link data-trunk rel="rust" data-bin="app">"#);
+        assert!(pipeline.contains("--manifest-path=Cargo.toml --bin=app"));
+    }
+
+    #[test]
+    fn css_pipeline() {
+        let pipeline = pipeline_of(r#"<link data-trunk rel="css" href="style/main.css">"#);
+        assert!(pipeline.starts_with("**Trunk pipeline**"));
+        assert!(pipeline.contains("main-{hash}.css"));
+    }
+
+    #[test]
+    fn inline_type_from_extension() {
+        assert_eq!(infer_inline_type("a/b.html"), Some("html"));
+        assert_eq!(infer_inline_type("icon.svg"), Some("svg"));
+        assert_eq!(infer_inline_type("style.css"), Some("css"));
+        assert_eq!(infer_inline_type("main.js"), Some("js"));
+        assert_eq!(infer_inline_type("main.mjs"), Some("mjs"));
+        assert_eq!(infer_inline_type("notes.txt"), None);
+        assert_eq!(infer_inline_type("LICENSE"), None);
+    }
+}
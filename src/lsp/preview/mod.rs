@@ -0,0 +1,220 @@
+use anyhow::Context;
+use tree_sitter::{Node, Parser};
+
+use crate::attr_state::{AssetType, ElemAttrs, TagName, TrunkAttrState};
+
+use super::{pipeline::HASH, queries::attributes::trunk_elements};
+
+/// The command name used with `workspace/executeCommand` to preview the output HTML.
+pub const PREVIEW_COMMAND: &str = "trunkls.previewHtml";
+
+/// Parses the provided HTML and returns an approximation of the HTML Trunk will output.
+pub fn preview_source(s: &str) -> anyhow::Result<String> {
+    let mut parser = Parser::new();
+    parser.set_language(&tree_sitter_html::LANGUAGE.into())?;
+    let tree = parser
+        .parse(s, None)
+        .context("Tree not returned during parsing")?;
+
+    Ok(preview(tree.root_node(), s))
+}
+
+/// Returns an approximation of the HTML Trunk will output.
+///
+/// Each Trunk element is replaced with the HTML its pipeline generates. Content hashes are not
+/// calculated and are instead shown as placeholders.
+pub fn preview(n: Node, s: &str) -> String {
+    let mut replacements = vec![];
+    for tag in trunk_elements(n, s) {
+        let mut c = tag.walk();
+        let Some(attr_state) = TrunkAttrState::from_elem_items(s, tag.named_children(&mut c))
+        else {
+            continue;
+        };
+
+        // The element node is the start tag, the replacement should also cover the content and
+        // end tag of script elements.
+        let elem = tag
+            .parent()
+            .filter(|p| matches!(p.kind(), "element" | "script_element"))
+            .unwrap_or(tag);
+        let attrs = ElemAttrs::from_elem(s, tag);
+        replacements.push((elem.byte_range(), attr_state.preview_html(&attrs)));
+    }
+
+    let mut out = String::with_capacity(s.len());
+    let mut last = 0;
+    for (range, html) in replacements {
+        out.push_str(&s[last..range.start]);
+        // Keep multi line replacements aligned with the element they replace.
+        let line_start = s[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let indent = &s[line_start..range.start];
+        if indent.chars().all(char::is_whitespace) {
+            out.push_str(&html.replace('\n', &String::from_iter(["\n", indent])));
+        } else {
+            out.push_str(&html);
+        }
+        last = range.end;
+    }
+    out.push_str(&s[last..]);
+
+    out
+}
+
+impl TrunkAttrState {
+    /// The HTML Trunk outputs in place of the element.
+    fn preview_html(&self, attrs: &ElemAttrs) -> String {
+        let url = self
+            .output_path(attrs)
+            .map(|path| String::from_iter(["/", &path]))
+            .unwrap_or_default();
+        let integrity = integrity_attr(
+            self.value_or_default(attrs, "data-integrity")
+                .unwrap_or("sha384"),
+        );
+
+        match (self.tag_name, self.rel) {
+            (TagName::Script, _) => {
+                let mut html = format!("<script src=\"{url}\"{integrity}");
+                for attr in attrs
+                    .attrs
+                    .iter()
+                    .filter(|attr| attr.name != "src" && !attr.name.starts_with("data-"))
+                {
+                    html.push(' ');
+                    html.push_str(attr.name);
+                    if let Some(val) = attr.value {
+                        html.push_str("=\"");
+                        html.push_str(val);
+                        html.push('"');
+                    }
+                }
+                html.push_str("></script>");
+                html
+            }
+            (_, Some(AssetType::Rust)) => self.preview_rust(attrs, &url, &integrity),
+            (_, Some(AssetType::Css)) => {
+                format!("<link rel=\"stylesheet\" href=\"{url}\"{integrity}/>")
+            }
+            (_, Some(AssetType::Sass | AssetType::Scss | AssetType::Tailwind)) => {
+                if attrs.has("data-inline") {
+                    format!(
+                        "<style>/* compiled {} */</style>",
+                        attrs.value("href").unwrap_or_default()
+                    )
+                } else {
+                    format!("<link rel=\"stylesheet\" href=\"{url}\"{integrity}/>")
+                }
+            }
+            (_, Some(AssetType::Icon)) => format!("<link rel=\"icon\" href=\"{url}\"{integrity}/>"),
+            (_, Some(AssetType::Inline)) => {
+                let href = attrs.value("href").unwrap_or_default();
+                match self.inline_type(attrs) {
+                    Some("css") => format!("<style>/* content of {href} */</style>"),
+                    Some("js") => format!("<script>/* content of {href} */</script>"),
+                    Some("mjs" | "module") => {
+                        format!("<script type=\"module\">/* content of {href} */</script>")
+                    }
+                    _ => format!("<!-- content of {href} -->"),
+                }
+            }
+            // Copied assets are only written to the dist directory.
+            (_, Some(AssetType::CopyFile | AssetType::CopyDir)) => String::new(),
+            (_, None) => String::new(),
+        }
+    }
+
+    fn preview_rust(&self, attrs: &ElemAttrs, url: &str, integrity: &str) -> String {
+        let cross_origin = self
+            .value_or_default(attrs, "data-cross-origin")
+            .unwrap_or_default();
+        let wasm_url = format!("{}_bg.wasm", url.strip_suffix(".js").unwrap_or(url));
+        if self.value_or_default(attrs, "data-type") == Some("worker") {
+            return format!("<!-- worker: {url} and {wasm_url} are written to dist -->");
+        }
+
+        let import_name = (!attrs.has("data-wasm-no-import")).then(|| {
+            attrs
+                .value("data-wasm-import-name")
+                .unwrap_or("wasmBindings")
+        });
+        let started =
+            "dispatchEvent(new CustomEvent(\"TrunkApplicationStarted\", {detail: {wasm}}));\n";
+
+        match self.value_or_default(attrs, "data-bindgen-target") {
+            Some("web") => {
+                let mut script = format!(
+                    "import init, * as bindings from '{url}';\nconst wasm = await init({{ module_or_path: '{wasm_url}' }});\n"
+                );
+                if let Some(name) = import_name {
+                    script.push_str(&format!("window.{name} = bindings;\n"));
+                }
+                script.push_str(started);
+
+                format!(
+                    "<link rel=\"modulepreload\" href=\"{url}\" crossorigin=\"{cross_origin}\"{integrity}>\n\
+                     <link rel=\"preload\" href=\"{wasm_url}\" crossorigin=\"{cross_origin}\"{integrity} as=\"fetch\" type=\"application/wasm\">\n\
+                     <script type=\"module\">\n{script}</script>"
+                )
+            }
+            // The bindings are a global `wasm_bindgen` loaded by a classic script.
+            Some("no-modules") => {
+                let mut script = format!(
+                    "wasm_bindgen({{ module_or_path: '{wasm_url}' }}).then((wasm) => {{\n"
+                );
+                if let Some(name) = import_name {
+                    script.push_str(&format!("window.{name} = wasm_bindgen;\n"));
+                }
+                script.push_str(started);
+                script.push_str("});\n");
+
+                format!(
+                    "<link rel=\"preload\" href=\"{wasm_url}\" crossorigin=\"{cross_origin}\"{integrity} as=\"fetch\" type=\"application/wasm\">\n\
+                     <script src=\"{url}\" crossorigin=\"{cross_origin}\"{integrity}></script>\n\
+                     <script>\n{script}</script>"
+                )
+            }
+            target => format!(
+                "<!-- {url} and {wasm_url} are written to dist, the `{}` target is not loaded by Trunk -->",
+                target.unwrap_or_default()
+            ),
+        }
+    }
+}
+
+fn integrity_attr(alg: &str) -> String {
+    match alg {
+        "none" => String::new(),
+        alg => format!(" integrity=\"{alg}-{HASH}\""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::preview_source;
+
+    #[test]
+    fn rust_bindgen_targets() {
+        let classic = preview_source(r#"<link data-trunk rel="rust">"#).unwrap();
+        assert!(classic.contains("<script src=\"/{crate}-{hash}.js\""));
+        assert!(classic.contains("wasm_bindgen("));
+        assert!(!classic.contains("modulepreload"));
+
+        let module =
+            preview_source(r#"<link data-trunk rel="rust" data-bindgen-target="web">"#).unwrap();
+        assert!(module.contains("<link rel=\"modulepreload\" href=\"/{crate}-{hash}.js\""));
+        assert!(module.contains("import init, * as bindings"));
+        assert!(module.contains("window.wasmBindings = bindings;"));
+
+        let renamed = preview_source(
+            r#"<link data-trunk rel="rust" data-bindgen-target="web" data-wasm-import-name="app">"#,
+        )
+        .unwrap();
+        assert!(renamed.contains("window.app = bindings;"));
+        let no_import = preview_source(
+            r#"<link data-trunk rel="rust" data-bindgen-target="web" data-wasm-no-import>"#,
+        )
+        .unwrap();
+        assert!(!no_import.contains("window."));
+    }
+}
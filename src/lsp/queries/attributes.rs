@@ -1,6 +1,10 @@
 use std::sync::LazyLock;
 
-use tree_sitter::Query;
+use fxhash::FxHashSet;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Node, Query, QueryCursor};
+
+use crate::attr_state::ElemAttrs;
 
 /// Query for all elements containing a `data-trunk` attribute.
 pub static TRUNK_ATTRS: LazyLock<Query> = LazyLock::new(|| {
@@ -14,3 +18,66 @@ pub static TRUNK_ATTRS: LazyLock<Query> = LazyLock::new(|| {
 "#;
     Query::new(&tree_sitter_html::LANGUAGE.into(), QS).unwrap()
 });
+
+/// Query for all link and script elements.
+pub static LINK_SCRIPT_TAGS: LazyLock<Query> = LazyLock::new(|| {
+    const QS: &str = r#"
+    (_
+            (tag_name) @tag.name
+            (#any-of? @tag.name "link" "script")
+    ) @element
+"#;
+    Query::new(&tree_sitter_html::LANGUAGE.into(), QS).unwrap()
+});
+
+/// Query for all style and script elements.
+pub static STYLE_SCRIPT_ELEMENTS: LazyLock<Query> = LazyLock::new(|| {
+    const QS: &str = r#"
+    [(style_element) (script_element)] @element
+"#;
+    Query::new(&tree_sitter_html::LANGUAGE.into(), QS).unwrap()
+});
+
+/// Returns every style and script element, including their content, in document order.
+pub fn style_script_elements<'a>(n: Node<'a>, s: &str) -> Vec<Node<'a>> {
+    query_elements(&STYLE_SCRIPT_ELEMENTS, n, s)
+}
+
+/// Returns the start tag of every link and script element in document order.
+pub fn link_script_elements<'a>(n: Node<'a>, s: &str) -> Vec<Node<'a>> {
+    let mut elems = query_elements(&LINK_SCRIPT_TAGS, n, s);
+    // End tags also contain a tag name.
+    elems.retain(|elem| matches!(elem.kind(), "start_tag" | "self_closing_tag"));
+    elems
+}
+
+/// Returns the start tag of every element containing a `data-trunk` attribute in document order.
+pub fn trunk_elements<'a>(n: Node<'a>, s: &str) -> Vec<Node<'a>> {
+    let mut elems = query_elements(&TRUNK_ATTRS, n, s);
+    // `any-eq?` does not reject matches of the quantified attributes, so it is checked again.
+    elems.retain(|elem| ElemAttrs::from_elem(s, *elem).has("data-trunk"));
+    elems
+}
+
+fn query_elements<'a>(query: &Query, n: Node<'a>, s: &str) -> Vec<Node<'a>> {
+    let element_id = query
+        .capture_names()
+        .iter()
+        .position(|e| *e == "element")
+        .unwrap() as u32;
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, n, s.as_bytes());
+
+    let mut seen = FxHashSet::default();
+    let mut elems = vec![];
+    while let Some(qm) = matches.next() {
+        for cap in qm.captures.iter().filter(|cap| cap.index == element_id) {
+            if seen.insert(cap.node.id()) {
+                elems.push(cap.node);
+            }
+        }
+    }
+    elems.sort_by_key(|n| n.start_byte());
+
+    elems
+}
@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use lsp_types::{Location, Uri};
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::{AssetType, ElemAttrs, TrunkAttrState},
+    utils::{find_attr, find_elem, node_range, normalize_path, uri_to_path},
+};
+use texter::{change::GridIndex, core::text::Text};
+
+use super::{document::Documents, queries::attributes::trunk_elements, workspace::for_each_file};
+
+/// The attributes that point to the source file of an asset.
+const SOURCE_ATTRS: &[&str] = &["href", "src"];
+
+/// Returns the file referenced by the `href` or `src` value at the position of an HTML document.
+pub fn referenced_path(uri: &Uri, pos: GridIndex, n: Node, text: &Text) -> Option<PathBuf> {
+    let s = text.text.as_str();
+    let in_pos = n.named_descendant_for_point_range(pos.into(), pos.into())?;
+    let attr = find_attr(in_pos)?;
+    let elem = find_elem(attr)?;
+
+    let mut cursor = elem.walk();
+    let attr_state = TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))?;
+    if !attr_state.data_trunk {
+        return None;
+    }
+    let attrs = ElemAttrs::from_elem(s, elem);
+    let name = attrs.attrs.iter().find(|a| a.node == attr)?.name;
+    if !SOURCE_ATTRS.contains(&name) {
+        return None;
+    }
+
+    let dir = uri_to_path(uri)?.parent()?.to_path_buf();
+    attr_state.source_path(&dir, &attrs)
+}
+
+/// Returns the locations of all Trunk elements in the workspace that load the file.
+#[allow(clippy::mutable_key_type)]
+pub fn references(target: &Path, docs: &Documents) -> Vec<Location> {
+    let target = normalize_path(target);
+    let mut locations = vec![];
+    for_each_file(docs, |uri, tree, text| {
+        let Some(dir) = uri_to_path(uri).and_then(|html| Some(html.parent()?.to_path_buf())) else {
+            return;
+        };
+        let s = text.text.as_str();
+        for elem in trunk_elements(tree.root_node(), s) {
+            let mut cursor = elem.walk();
+            let Some(attr_state) =
+                TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))
+            else {
+                continue;
+            };
+            let attrs = ElemAttrs::from_elem(s, elem);
+            if attr_state.source_path(&dir, &attrs).as_ref() != Some(&target) {
+                continue;
+            }
+
+            // Rust assets without an `href` are reported on the element itself.
+            let n = SOURCE_ATTRS
+                .iter()
+                .find_map(|name| attrs.get(name))
+                .map(|attr| attr.value_node.unwrap_or(attr.node))
+                .unwrap_or(elem);
+            let Some(range) = node_range(text, n) else {
+                continue;
+            };
+            locations.push(Location {
+                uri: uri.clone(),
+                range,
+            });
+        }
+    });
+
+    locations
+}
+
+impl TrunkAttrState {
+    /// The normalized path of the file the asset is built from.
+    ///
+    /// Rust assets resolve to their `Cargo.toml`, which is in the directory of the HTML file if
+    /// there is no `href`.
+    pub fn source_path(&self, dir: &Path, attrs: &ElemAttrs) -> Option<PathBuf> {
+        let source = SOURCE_ATTRS.iter().find_map(|name| attrs.value(name));
+        let path = match (self.rel, source) {
+            (Some(AssetType::Rust), None) => dir.join("Cargo.toml"),
+            (Some(AssetType::Rust), Some(href)) if !href.ends_with("Cargo.toml") => {
+                dir.join(href).join("Cargo.toml")
+            }
+            (_, source) => dir.join(source?),
+        };
+
+        Some(normalize_path(&path))
+    }
+}
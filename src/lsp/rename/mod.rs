@@ -0,0 +1,344 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use lsp_types::{Range, RenameFilesParams, TextEdit, Uri, WorkspaceEdit};
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::{AssetType, ElemAttrs, TrunkAttrState},
+    init::TextFn,
+    utils::{
+        byte_range, find_attr, find_elem, is_local, node_range, normalize_path, path_to_uri,
+        relative_path, uri_to_path,
+    },
+};
+use texter::{change::GridIndex, core::text::Text};
+
+use super::{
+    cargo_toml::bin_name_ranges, document::Documents, queries::attributes::trunk_elements,
+    workspace::for_each_file,
+};
+
+/// The attributes containing paths relative to the HTML file.
+const PATH_ATTRS: &[&str] = &["href", "src", "data-initializer"];
+
+/// Returns the edits that keep the paths of Trunk elements pointing to renamed files and
+/// directories.
+///
+/// The paths of HTML files that are moved themselves are made relative to their new directory.
+#[allow(clippy::mutable_key_type)]
+pub fn will_rename_files(p: RenameFilesParams, docs: &Documents) -> Option<WorkspaceEdit> {
+    let renames: Vec<(PathBuf, PathBuf)> = p
+        .files
+        .iter()
+        .filter_map(|rename| {
+            let old = uri_to_path(&Uri::from_str(&rename.old_uri).ok()?)?;
+            let new = uri_to_path(&Uri::from_str(&rename.new_uri).ok()?)?;
+            Some((normalize_path(&old), normalize_path(&new)))
+        })
+        .collect();
+
+    let mut changes: HashMap<Uri, Vec<TextEdit>> = HashMap::new();
+    for_each_file(docs, |uri, tree, text| {
+        let Some(html) = uri_to_path(uri).map(|html| normalize_path(&html)) else {
+            return;
+        };
+        let Some(dir) = html.parent() else {
+            return;
+        };
+        let new_html = renamed_path(&renames, &html);
+        let new_dir = new_html.as_deref().and_then(Path::parent).unwrap_or(dir);
+        let s = text.text.as_str();
+        for elem in trunk_elements(tree.root_node(), s) {
+            let attrs = ElemAttrs::from_elem(s, elem);
+            for attr in attrs
+                .attrs
+                .iter()
+                .filter(|attr| PATH_ATTRS.contains(&attr.name))
+            {
+                // Absolute paths and remote resources do not depend on the location of the HTML.
+                let Some(value) = attr
+                    .value
+                    .filter(|v| !v.is_empty() && !v.starts_with('/') && is_local(v))
+                else {
+                    continue;
+                };
+                let path = normalize_path(&dir.join(value));
+                let new_path = renamed_path(&renames, &path);
+                if new_path.is_none() && new_html.is_none() {
+                    continue;
+                }
+                let new_text = relative_path(new_dir, new_path.as_deref().unwrap_or(&path));
+                if new_text == value || new_text == relative_path(dir, &path) {
+                    continue;
+                }
+                // Only the content between the quotes is replaced.
+                let Some(range) = attr.content_node().and_then(|n| node_range(text, n)) else {
+                    continue;
+                };
+
+                changes
+                    .entry(uri.clone())
+                    .or_default()
+                    .push(TextEdit { range, new_text });
+            }
+        }
+    });
+
+    if changes.is_empty() {
+        return None;
+    }
+
+    Some(WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    })
+}
+
+/// The new location of the path if it or one of its parent directories is renamed.
+fn renamed_path(renames: &[(PathBuf, PathBuf)], path: &Path) -> Option<PathBuf> {
+    renames.iter().find_map(|(old, new)| {
+        let rest = path.strip_prefix(old).ok()?;
+        Some(match rest.as_os_str().is_empty() {
+            true => new.clone(),
+            false => new.join(rest),
+        })
+    })
+}
+
+/// A `data-bin` value of a Rust asset.
+struct BinRef<'a> {
+    /// The `Cargo.toml` of the crate the binary belongs to.
+    cargo_toml: PathBuf,
+    name: &'a str,
+    /// The node containing the value without its quotes.
+    value_node: Node<'a>,
+}
+
+/// Returns the `data-bin` value at the position.
+fn bin_at<'a>(uri: &Uri, pos: GridIndex, n: Node<'a>, text: &'a Text) -> Option<BinRef<'a>> {
+    let s = text.text.as_str();
+    let in_pos = n.named_descendant_for_point_range(pos.into(), pos.into())?;
+    let attr = find_attr(in_pos)?;
+    let elem = find_elem(attr)?;
+
+    let mut cursor = elem.walk();
+    let attr_state = TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))?;
+    if !attr_state.data_trunk || attr_state.rel != Some(AssetType::Rust) {
+        return None;
+    }
+    let attrs = ElemAttrs::from_elem(s, elem);
+    let attr = attrs
+        .attrs
+        .iter()
+        .find(|a| a.node == attr && a.name == "data-bin")?;
+    let name = attr.value.filter(|v| !v.is_empty())?;
+    let value_node = attr.content_node()?;
+
+    let dir = uri_to_path(uri)?.parent()?.to_path_buf();
+    Some(BinRef {
+        cargo_toml: attr_state.source_path(&dir, &attrs)?,
+        name,
+        value_node,
+    })
+}
+
+/// Reads the `Cargo.toml` and returns its content along with the ranges of the binary name.
+fn read_bin(bin: &BinRef) -> Result<(String, Vec<std::ops::Range<usize>>), String> {
+    let s = std::fs::read_to_string(&bin.cargo_toml)
+        .map_err(|err| format!("Unable to read {:?}: {err}", bin.cargo_toml))?;
+    let ranges = bin_name_ranges(&s, bin.name).ok_or_else(|| {
+        format!(
+            "`{}` is not the name of a `[[bin]]` in {:?}.",
+            bin.name, bin.cargo_toml
+        )
+    })?;
+
+    Ok((s, ranges))
+}
+
+/// Returns the range of the `data-bin` value at the position if it names a `[[bin]]` of the
+/// crate.
+pub fn prepare_rename(
+    uri: &Uri,
+    pos: GridIndex,
+    n: Node,
+    text: &Text,
+) -> Result<Option<Range>, String> {
+    let Some(bin) = bin_at(uri, pos, n, text) else {
+        return Ok(None);
+    };
+    read_bin(&bin)?;
+
+    Ok(node_range(text, bin.value_node))
+}
+
+/// Renames the binary of the `data-bin` value at the position in the `Cargo.toml` of the crate
+/// and in all Trunk elements of the workspace building it.
+#[allow(clippy::mutable_key_type)]
+pub fn rename(
+    uri: &Uri,
+    pos: GridIndex,
+    new_name: &str,
+    text_fn: TextFn,
+    docs: &Documents,
+) -> Result<Option<WorkspaceEdit>, String> {
+    let Some((tree, text)) = docs.get(uri) else {
+        return Ok(None);
+    };
+    let Some(bin) = bin_at(uri, pos, tree.root_node(), text) else {
+        return Ok(None);
+    };
+    if new_name.is_empty()
+        || !new_name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("`{new_name}` is not a valid binary name."));
+    }
+    let (cargo_s, ranges) = read_bin(&bin)?;
+
+    let mut changes: HashMap<Uri, Vec<TextEdit>> = HashMap::new();
+    let cargo_uri = path_to_uri(&bin.cargo_toml)
+        .ok_or_else(|| format!("Unable to create a URI for {:?}.", bin.cargo_toml))?;
+    let cargo_text = text_fn(cargo_s);
+    changes.insert(
+        cargo_uri,
+        ranges
+            .into_iter()
+            .filter_map(|range| {
+                Some(TextEdit {
+                    range: byte_range(&cargo_text, range)?,
+                    new_text: new_name.to_string(),
+                })
+            })
+            .collect(),
+    );
+
+    for_each_file(docs, |uri, tree, text| {
+        let Some(dir) = uri_to_path(uri).and_then(|html| Some(html.parent()?.to_path_buf())) else {
+            return;
+        };
+        let s = text.text.as_str();
+        for elem in trunk_elements(tree.root_node(), s) {
+            let mut cursor = elem.walk();
+            let Some(attr_state) =
+                TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))
+            else {
+                continue;
+            };
+            if attr_state.rel != Some(AssetType::Rust) {
+                continue;
+            }
+            let attrs = ElemAttrs::from_elem(s, elem);
+            if attrs.value("data-bin") != Some(bin.name)
+                || attr_state.source_path(&dir, &attrs).as_ref() != Some(&bin.cargo_toml)
+            {
+                continue;
+            }
+            let Some(range) = attrs
+                .get("data-bin")
+                .and_then(|attr| attr.content_node())
+                .and_then(|n| node_range(text, n))
+            else {
+                continue;
+            };
+
+            changes.entry(uri.clone()).or_default().push(TextEdit {
+                range,
+                new_text: new_name.to_string(),
+            });
+        }
+    });
+
+    Ok(Some(WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        str::FromStr,
+    };
+
+    use lsp_types::{FileRename, RenameFilesParams, Uri};
+    use texter::core::text::Text;
+    use tree_sitter::Parser;
+
+    use super::{renamed_path, will_rename_files};
+    use crate::lsp::document::Documents;
+
+    const HTML: &str = r#"<link data-trunk rel="css" href="styles/main.css">
+<link data-trunk rel="css" href="../shared/a.css">
+<script data-trunk src="https://example.com/a.js"></script>"#;
+
+    /// The new texts of the edits to `/ws/site/index.html` for the rename.
+    #[allow(clippy::mutable_key_type)]
+    fn rename_edits(old: &str, new: &str) -> Vec<String> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_html::LANGUAGE.into())
+            .unwrap();
+        let uri = Uri::from_str("file:///ws/site/index.html").unwrap();
+        let mut docs = Documents::default();
+        docs.insert(
+            uri.clone(),
+            (
+                parser.parse(HTML, None).unwrap(),
+                Text::new(HTML.to_string()),
+            ),
+        );
+
+        let p = RenameFilesParams {
+            files: vec![FileRename {
+                old_uri: format!("file://{old}"),
+                new_uri: format!("file://{new}"),
+            }],
+        };
+        let Some(edit) = will_rename_files(p, &docs) else {
+            return vec![];
+        };
+        edit.changes.unwrap()[&uri]
+            .iter()
+            .map(|edit| edit.new_text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn renamed_paths() {
+        let renames = [(PathBuf::from("/ws/site"), PathBuf::from("/ws/web"))];
+        let renamed = |path| renamed_path(&renames, Path::new(path));
+        assert_eq!(
+            renamed("/ws/site/a.css"),
+            Some(PathBuf::from("/ws/web/a.css"))
+        );
+        assert_eq!(renamed("/ws/site"), Some(PathBuf::from("/ws/web")));
+        assert_eq!(renamed("/ws/sites/a.css"), None);
+        assert_eq!(renamed("/ws/a.css"), None);
+    }
+
+    #[test]
+    fn rename_file_edits() {
+        assert_eq!(
+            rename_edits("/ws/site/styles", "/ws/site/css"),
+            ["css/main.css"]
+        );
+        assert_eq!(
+            rename_edits("/ws/shared/a.css", "/ws/shared/b.css"),
+            ["../shared/b.css"]
+        );
+        // Paths inside a moved directory stay the same.
+        assert!(rename_edits("/ws/site", "/ws/web").is_empty());
+        // The paths of a moved HTML file are relative to its new directory.
+        assert_eq!(
+            rename_edits("/ws/site/index.html", "/ws/site/pages/index.html"),
+            ["../styles/main.css", "../../shared/a.css"]
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use lsp_types::{
+    Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensLegend,
+};
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::{AssetType, ElemAttrs, TrunkAttrState},
+    lsp::docs::ValueRequirment,
+    utils::node_range,
+};
+use texter::core::text::Text;
+
+use super::queries::attributes::trunk_elements;
+
+const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::ENUM_MEMBER,
+];
+const TOKEN_MODIFIERS: &[SemanticTokenModifier] = &[
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::DEPRECATED,
+];
+
+/// Used for `data-trunk`.
+const KEYWORD: u32 = 0;
+/// Used for attribute names of the asset schema.
+const PROPERTY: u32 = 1;
+/// Used for values of attributes that accept a fixed set of values.
+const ENUM_MEMBER: u32 = 2;
+
+/// Set on values that are one of the accepted values.
+const READONLY: u32 = 1 << 0;
+/// Set on attributes and values that are not valid for the asset.
+const DEPRECATED: u32 = 1 << 1;
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// Returns the semantic tokens of all Trunk elements that intersect with the provided range, or
+/// the whole document if there is none.
+pub fn semantic_tokens(range: Option<Range>, n: Node, text: &Text) -> SemanticTokens {
+    let s = text.text.as_str();
+    let mut tokens = Tokens::default();
+    for elem in trunk_elements(n, s) {
+        let Some(elem_range) = node_range(text, elem) else {
+            continue;
+        };
+        if range.is_some_and(|range| {
+            elem_range.end.line < range.start.line || elem_range.start.line > range.end.line
+        }) {
+            continue;
+        }
+
+        let mut cursor = elem.walk();
+        let Some(attr_state) = TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))
+        else {
+            continue;
+        };
+        let attrs = ElemAttrs::from_elem(s, elem);
+        attr_state.push_tokens(text, &attrs, &mut tokens);
+    }
+
+    SemanticTokens {
+        result_id: None,
+        data: tokens.data,
+    }
+}
+
+/// Semantic tokens encoded relative to the previous token.
+#[derive(Default)]
+struct Tokens {
+    data: Vec<SemanticToken>,
+    line: u32,
+    start: u32,
+}
+
+impl Tokens {
+    /// Adds a token for the node, which must come after all previously added tokens.
+    ///
+    /// Nodes spanning multiple lines are skipped.
+    fn push(&mut self, text: &Text, n: Node, token_type: u32, modifiers: u32) {
+        let Some(range) = node_range(text, n).filter(|range| range.start.line == range.end.line)
+        else {
+            return;
+        };
+
+        let delta_line = range.start.line - self.line;
+        let delta_start = match delta_line {
+            0 => range.start.character - self.start,
+            _ => range.start.character,
+        };
+        self.data.push(SemanticToken {
+            delta_line,
+            delta_start,
+            length: range.end.character - range.start.character,
+            token_type,
+            token_modifiers_bitset: modifiers,
+        });
+        self.line = range.start.line;
+        self.start = range.start.character;
+    }
+}
+
+impl TrunkAttrState {
+    fn push_tokens(&self, text: &Text, attrs: &ElemAttrs, tokens: &mut Tokens) {
+        let schema = self.asset_attrs().unwrap_or_default();
+        for attr in attrs.attrs.iter() {
+            let Some(name_node) = attr.node.named_child(0) else {
+                continue;
+            };
+            let accepted: Option<Vec<&str>> = match attr.name {
+                "data-trunk" => {
+                    tokens.push(text, name_node, KEYWORD, 0);
+                    continue;
+                }
+                "rel" => {
+                    tokens.push(text, name_node, PROPERTY, 0);
+                    Some(AssetType::NAMES.to_vec())
+                }
+                name => match schema.iter().find(|(attr_name, _, _)| *attr_name == name) {
+                    Some((_, _, req)) => {
+                        tokens.push(text, name_node, PROPERTY, 0);
+                        match req {
+                            ValueRequirment::Values(_, vals) => {
+                                Some(vals.iter().map(|(v, _)| *v).collect())
+                            }
+                            _ => None,
+                        }
+                    }
+                    None if name.starts_with("data-") => {
+                        tokens.push(text, name_node, PROPERTY, DEPRECATED);
+                        None
+                    }
+                    None => None,
+                },
+            };
+
+            let (Some(accepted), Some(value), Some(value_node)) = (
+                accepted,
+                attr.value.filter(|v| !v.is_empty()),
+                attr.content_node(),
+            ) else {
+                continue;
+            };
+            let modifiers = match accepted.contains(&value) {
+                true => READONLY,
+                false => DEPRECATED,
+            };
+            tokens.push(text, value_node, ENUM_MEMBER, modifiers);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use lsp_types::{DocumentSymbol, Range, SymbolKind};
+use tree_sitter::Node;
+
+use crate::{
+    attr_state::{AssetType, ElemAttrs, TagName, TrunkAttrState},
+    utils::node_range,
+};
+use texter::core::text::Text;
+
+use super::queries::attributes::trunk_elements;
+
+/// Returns the Trunk elements of the document grouped by their asset type.
+///
+/// Groups are ordered by their first element and contain the elements in document order.
+pub fn document_symbols(n: Node, text: &Text) -> Vec<DocumentSymbol> {
+    let s = text.text.as_str();
+    let mut groups: Vec<DocumentSymbol> = vec![];
+    for elem in trunk_elements(n, s) {
+        let mut cursor = elem.walk();
+        let Some(attr_state) = TrunkAttrState::from_elem_items(s, elem.named_children(&mut cursor))
+        else {
+            continue;
+        };
+        let attrs = ElemAttrs::from_elem(s, elem);
+        let asset = attr_state.asset_name(&attrs);
+        let Some(symbol) = attr_state.symbol(text, elem, &attrs, asset) else {
+            continue;
+        };
+
+        match groups.iter_mut().find(|group| group.name == asset) {
+            Some(group) => {
+                group.range.end = symbol.range.end;
+                group.children.get_or_insert_with(Vec::new).push(symbol);
+            }
+            None => {
+                #[allow(deprecated)]
+                groups.push(DocumentSymbol {
+                    name: asset.to_string(),
+                    detail: None,
+                    kind: SymbolKind::NAMESPACE,
+                    tags: None,
+                    deprecated: None,
+                    range: symbol.range,
+                    selection_range: symbol.selection_range,
+                    children: Some(vec![symbol]),
+                });
+            }
+        }
+    }
+
+    groups
+}
+
+impl TrunkAttrState {
+    /// The name of the asset type, which is the `rel` value for links.
+    fn asset_name<'a>(&self, attrs: &ElemAttrs<'a>) -> &'a str {
+        match (self.tag_name, self.rel) {
+            (TagName::Script, _) => "script",
+            (_, Some(rel)) => rel.as_str(),
+            (_, None) => attrs.value("rel").unwrap_or("link"),
+        }
+    }
+
+    fn symbol(
+        &self,
+        text: &Text,
+        elem: Node,
+        attrs: &ElemAttrs,
+        asset: &str,
+    ) -> Option<DocumentSymbol> {
+        let name = attrs
+            .value("href")
+            .or_else(|| attrs.value("src"))
+            .map(str::to_string)
+            .unwrap_or_else(|| match self.rel {
+                // Trunk builds the crate in the directory of the HTML file by default.
+                Some(AssetType::Rust) => "Cargo.toml".to_string(),
+                _ => format!("<{asset}>"),
+            });
+        let kind = match self.rel {
+            Some(AssetType::CopyDir) => SymbolKind::PACKAGE,
+            Some(AssetType::Rust) => SymbolKind::MODULE,
+            _ => SymbolKind::FILE,
+        };
+
+        // The range covers the whole element, including the content and end tag of scripts.
+        let whole = elem
+            .parent()
+            .filter(|p| p.kind() == "script_element")
+            .unwrap_or(elem);
+
+        #[allow(deprecated)]
+        Some(DocumentSymbol {
+            name,
+            detail: Some(asset.to_string()),
+            kind,
+            tags: None,
+            deprecated: None,
+            range: node_range(text, whole)?,
+            selection_range: selection_range(text, elem, attrs)?,
+            children: None,
+        })
+    }
+}
+
+/// The range of the `href` or `src` value, or the start tag if there is none.
+fn selection_range(text: &Text, elem: Node, attrs: &ElemAttrs) -> Option<Range> {
+    let n = attrs
+        .get("href")
+        .or_else(|| attrs.get("src"))
+        .and_then(|attr| attr.value_node)
+        .unwrap_or(elem);
+    node_range(text, n)
+}
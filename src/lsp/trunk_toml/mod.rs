@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+
+use toml_edit::DocumentMut;
+use tracing::warn;
+
+/// The dist directory Trunk uses when none is configured.
+const DEFAULT_DIST: &str = "dist";
+
+/// Finds the `Trunk.toml` that applies to an HTML file by searching its directory and ancestors.
+pub fn find_trunk_toml(html: &Path) -> Option<PathBuf> {
+    html.ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Trunk.toml"))
+        .find(|path| path.is_file())
+}
+
+/// The directory Trunk outputs to when building the HTML file.
+///
+/// The `dist` value of the `[build]` section is resolved relative to the `Trunk.toml` it is
+/// declared in. Without a `Trunk.toml` the HTML file's directory is used as the project root.
+pub fn dist_dir(html: &Path) -> Option<PathBuf> {
+    let Some(trunk_toml) = find_trunk_toml(html) else {
+        return Some(html.parent()?.join(DEFAULT_DIST));
+    };
+    let root = trunk_toml.parent()?;
+
+    let dist = build_value(&trunk_toml, "dist", |dist| {
+        dist.as_str().map(str::to_string)
+    });
+
+    Some(root.join(dist.as_deref().unwrap_or(DEFAULT_DIST)))
+}
+
+/// Whether Trunk adds a content hash to the names of the outputs, set by `filehash` in the
+/// `[build]` section.
+pub fn filehash(html: &Path) -> bool {
+    find_trunk_toml(html)
+        .and_then(|trunk_toml| build_value(&trunk_toml, "filehash", |hash| hash.as_bool()))
+        .unwrap_or(true)
+}
+
+/// Reads a value of the `[build]` section of the `Trunk.toml`.
+fn build_value<T>(
+    trunk_toml: &Path,
+    key: &str,
+    f: impl FnOnce(&toml_edit::Item) -> Option<T>,
+) -> Option<T> {
+    let value = std::fs::read_to_string(trunk_toml)
+        .map_err(anyhow::Error::from)
+        .and_then(|s| Ok(s.parse::<DocumentMut>()?))
+        .map(|doc| {
+            doc.get("build")
+                .and_then(|build| build.get(key))
+                .and_then(f)
+        });
+    match value {
+        Ok(value) => value,
+        Err(err) => {
+            warn!("Unable to read {:?} -> {}", trunk_toml, err);
+            None
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+
+use fxhash::FxHashMap;
+use lsp_server::{Connection, Message, Request, RequestId};
+use lsp_types::{
+    notification::{DidChangeWatchedFiles, Notification},
+    request::{RegisterCapability, Request as _},
+    DidChangeWatchedFilesParams, DidChangeWatchedFilesRegistrationOptions, FileChangeType,
+    FileSystemWatcher, GlobPattern, Registration, RegistrationParams, Uri,
+};
+use tracing::{info, warn};
+use tree_sitter::{Parser, Tree};
+
+use crate::{
+    init::TextFn,
+    utils::{path_to_uri, uri_to_path},
+};
+use texter::core::text::Text;
+
+use super::html_files;
+
+/// The parsed HTML files of the workspace, including files the client has not opened.
+///
+/// Files are stored with their content on disk, open documents in `DOCUMENTS` take precedence.
+pub type Index = FxHashMap<PathBuf, (Uri, Tree, Text)>;
+pub static INDEX: LazyLock<Mutex<Index>> = LazyLock::new(Mutex::default);
+
+/// Directories that never contain HTML files Trunk builds from.
+const IGNORED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// Whether a directory below a workspace root is skipped while indexing.
+pub fn is_ignored_dir(name: &str) -> bool {
+    name.starts_with('.') || IGNORED_DIRS.contains(&name)
+}
+
+/// Indexes all HTML files below the workspace roots on a background thread.
+pub fn start_indexer(roots: Vec<PathBuf>, text_fn: TextFn) {
+    std::thread::spawn(move || {
+        let mut parser = Parser::new();
+        if let Err(err) = parser.set_language(&tree_sitter_html::LANGUAGE.into()) {
+            warn!("Unable to create the indexing parser -> {}", err);
+            return;
+        }
+        let files = html_files(&roots);
+        for path in files.iter() {
+            index_file(&mut parser, text_fn, path);
+        }
+        info!("Indexed {} HTML files", files.len());
+    });
+}
+
+/// Asks the client to notify the server about created, changed and deleted HTML files.
+pub fn register_file_watcher(con: &Connection) -> anyhow::Result<()> {
+    let options = DidChangeWatchedFilesRegistrationOptions {
+        watchers: vec![FileSystemWatcher {
+            glob_pattern: GlobPattern::String("**/*.html".to_string()),
+            kind: None,
+        }],
+    };
+    let params = RegistrationParams {
+        registrations: vec![Registration {
+            id: "trunkls/watchHtml".to_string(),
+            method: DidChangeWatchedFiles::METHOD.to_string(),
+            register_options: Some(serde_json::to_value(options)?),
+        }],
+    };
+    con.sender.send(Message::Request(Request::new(
+        RequestId::from("trunkls/registerWatcher".to_string()),
+        RegisterCapability::METHOD.to_string(),
+        params,
+    )))?;
+
+    Ok(())
+}
+
+/// Updates the index with the file changes reported by the client.
+pub fn update_index(
+    parser: &mut Parser,
+    text_fn: TextFn,
+    roots: &[PathBuf],
+    p: DidChangeWatchedFilesParams,
+) {
+    for change in p.changes {
+        let Some(path) = uri_to_path(&change.uri) else {
+            continue;
+        };
+        if !is_indexed(roots, &path) {
+            continue;
+        }
+        match change.typ {
+            FileChangeType::DELETED => {
+                INDEX.lock().unwrap().remove(&path);
+            }
+            _ => index_file(parser, text_fn, &path),
+        }
+    }
+}
+
+/// Whether the file is an HTML file inside a workspace root that is not in an ignored directory.
+fn is_indexed(roots: &[PathBuf], path: &Path) -> bool {
+    if path.extension().is_none_or(|ext| ext != "html") {
+        return false;
+    }
+    roots.iter().any(|root| {
+        let Ok(rel) = path.strip_prefix(root) else {
+            return false;
+        };
+        let Some(dirs) = rel.parent() else {
+            return true;
+        };
+        dirs.components().all(|comp| {
+            comp.as_os_str()
+                .to_str()
+                .is_some_and(|c| !is_ignored_dir(c))
+        })
+    })
+}
+
+/// Parses the file and stores it in the index, removing it if it cannot be read.
+fn index_file(parser: &mut Parser, text_fn: TextFn, path: &Path) {
+    let s = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(err) => {
+            warn!("Unable to read {:?} -> {}", path, err);
+            INDEX.lock().unwrap().remove(path);
+            return;
+        }
+    };
+    let Some(uri) = path_to_uri(path) else {
+        return;
+    };
+    let Some(tree) = parser.parse(s.as_bytes(), None) else {
+        return;
+    };
+    let text = text_fn(s);
+    INDEX
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), (uri, tree, text));
+}
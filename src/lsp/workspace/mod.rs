@@ -0,0 +1,129 @@
+mod index;
+
+use std::path::{Path, PathBuf};
+
+use fxhash::FxHashSet;
+use lsp_types::{Location, OneOf, Uri, WorkspaceSymbol};
+use tracing::warn;
+use tree_sitter::Tree;
+
+use crate::utils::uri_to_path;
+use texter::core::text::Text;
+
+use super::{document::Documents, symbols::document_symbols};
+
+use index::is_ignored_dir;
+pub use index::{register_file_watcher, start_indexer, update_index, INDEX};
+
+/// Returns every HTML file below the workspace roots, skipping hidden and build directories.
+pub fn html_files(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = vec![];
+    let mut dirs = roots.to_vec();
+    while let Some(dir) = dirs.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("Unable to read {:?} -> {}", dir, err);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if !is_ignored_dir(name) {
+                    dirs.push(path);
+                }
+            } else if path.extension().is_some_and(|ext| ext == "html") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    files
+}
+
+/// Calls the function with every HTML file of the workspace.
+///
+/// Open documents are passed in their current state, all other files as they were indexed.
+#[allow(clippy::mutable_key_type)]
+pub fn for_each_file(docs: &Documents, mut f: impl FnMut(&Uri, &Tree, &Text)) {
+    let mut open = FxHashSet::default();
+    for (uri, (tree, text)) in docs.iter() {
+        if let Some(path) = uri_to_path(uri) {
+            open.insert(path);
+        }
+        f(uri, tree, text);
+    }
+
+    let index = INDEX.lock().unwrap();
+    let mut files: Vec<_> = index
+        .iter()
+        .filter(|(path, _)| !open.contains(*path))
+        .collect();
+    files.sort_by_key(|(path, _)| *path);
+    for (_, (uri, tree, text)) in files {
+        f(uri, tree, text);
+    }
+}
+
+/// Returns the Trunk assets of all HTML files in the workspace whose source or asset type
+/// contains the query.
+#[allow(clippy::mutable_key_type)]
+pub fn workspace_symbols(query: &str, roots: &[PathBuf], docs: &Documents) -> Vec<WorkspaceSymbol> {
+    let query = query.to_lowercase();
+    let mut symbols = vec![];
+    for_each_file(docs, |uri, tree, text| {
+        push_symbols(&query, roots, uri, tree, text, &mut symbols)
+    });
+
+    symbols
+}
+
+fn push_symbols(
+    query: &str,
+    roots: &[PathBuf],
+    uri: &Uri,
+    tree: &Tree,
+    text: &Text,
+    symbols: &mut Vec<WorkspaceSymbol>,
+) {
+    let file = uri_to_path(uri)
+        .map(|path| relative_to_roots(roots, &path))
+        .unwrap_or_else(|| uri.as_str().to_string());
+
+    for group in document_symbols(tree.root_node(), text) {
+        for symbol in group.children.unwrap_or_default() {
+            if !symbol.name.to_lowercase().contains(query) && !group.name.contains(query) {
+                continue;
+            }
+            symbols.push(WorkspaceSymbol {
+                name: symbol.name,
+                kind: symbol.kind,
+                tags: None,
+                container_name: Some(format!("{file} ({})", group.name)),
+                location: OneOf::Left(Location {
+                    uri: uri.clone(),
+                    range: symbol.selection_range,
+                }),
+                data: None,
+            });
+        }
+    }
+}
+
+/// The path relative to the workspace root containing it.
+fn relative_to_roots(roots: &[PathBuf], path: &Path) -> String {
+    roots
+        .iter()
+        .find_map(|root| path.strip_prefix(root).ok())
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
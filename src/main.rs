@@ -3,12 +3,15 @@ mod init;
 mod lsp;
 mod utils;
 
-use init::initialize_result;
-use lsp::main_loop;
+use init::{initialize_result, supports_file_watching, workspace_roots, ClientCaps};
+use lsp::{
+    main_loop,
+    workspace::{register_file_watcher, start_indexer},
+};
 
 use std::{fs::File, io::stderr};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use lsp_server::Connection;
 use lsp_types::InitializeParams;
 use tracing_subscriber::EnvFilter;
@@ -19,6 +22,14 @@ struct Config {
     log_file: Option<String>,
     #[arg(long = "version", action)]
     version: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print an approximation of the HTML Trunk outputs for an HTML file.
+    Preview { path: String },
 }
 
 fn main() -> anyhow::Result<()> {
@@ -27,10 +38,17 @@ fn main() -> anyhow::Result<()> {
         println!(env!("CARGO_PKG_VERSION"));
         return Ok(());
     }
+    if let Some(Command::Preview { path }) = cfg.command {
+        let s = std::fs::read_to_string(shellexpand::full(&path)?.as_ref())?;
+        print!("{}", lsp::preview::preview_source(&s)?);
+        return Ok(());
+    }
     let (con, _th) = Connection::stdio();
     let (id, resp) = con.initialize_start()?;
     let resp: InitializeParams = serde_json::from_value(resp)?;
     let (text_fn, init_res) = initialize_result(&resp);
+    let roots = workspace_roots(&resp);
+    ClientCaps::init(&resp);
     con.initialize_finish(id, serde_json::to_value(init_res)?)?;
 
     if let Some(lf) = cfg.log_file.as_deref().map(shellexpand::full) {
@@ -39,7 +57,12 @@ fn main() -> anyhow::Result<()> {
         log_to_stdout();
     }
 
-    main_loop(text_fn, con)?;
+    start_indexer(roots.clone(), text_fn);
+    if supports_file_watching(&resp) {
+        register_file_watcher(&con)?;
+    }
+
+    main_loop(text_fn, roots, con)?;
 
     Ok(())
 }
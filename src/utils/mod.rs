@@ -1,4 +1,11 @@
-use tree_sitter::Node;
+use std::{
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use lsp_types::{Range, TextEdit, Uri};
+use texter::{change::GridIndex, core::text::Text};
+use tree_sitter::{Node, Point};
 
 pub fn is_attr_name_completion(kind: &str) -> bool {
     matches!(
@@ -40,3 +47,202 @@ pub fn find_elem(mut n: Node) -> Option<Node> {
 
     Some(n)
 }
+
+/// Converts a pair of tree-sitter points to a range in the client's position encoding.
+///
+/// Returns `None` if a point is outside of the text.
+pub fn point_range(text: &Text, start: Point, end: Point) -> Option<Range> {
+    let mut start = GridIndex::from(start);
+    start.denormalize(text).ok()?;
+    let mut end = GridIndex::from(end);
+    end.denormalize(text).ok()?;
+    Some(Range {
+        start: start.into(),
+        end: end.into(),
+    })
+}
+
+/// Converts a range of byte offsets into the text to a range in the client's position encoding.
+pub fn byte_range(text: &Text, range: std::ops::Range<usize>) -> Option<Range> {
+    let point = |offset: usize| {
+        let before = text.text.get(..offset)?;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Some(Point::new(
+            before.matches('\n').count(),
+            offset - line_start,
+        ))
+    };
+    point_range(text, point(range.start)?, point(range.end)?)
+}
+
+pub fn node_range(text: &Text, n: Node) -> Option<Range> {
+    point_range(text, n.start_position(), n.end_position())
+}
+
+/// Converts a URI with a `file` scheme to a path.
+pub fn uri_to_path(uri: &Uri) -> Option<PathBuf> {
+    if uri.scheme()?.as_str() != "file" {
+        return None;
+    }
+    let path = uri.path().as_estr().decode().into_string().ok()?;
+
+    // Windows paths are written as "/C:/..." in URIs.
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        return Some(PathBuf::from(&path[1..]));
+    }
+
+    Some(PathBuf::from(path.as_ref()))
+}
+
+/// Whether the path refers to a file of the project rather than a remote resource.
+pub fn is_local(path: &str) -> bool {
+    !(path.contains("://") || path.starts_with("//") || path.starts_with("data:"))
+}
+
+/// Resolves `.` and `..` components without accessing the file system.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(comp);
+                }
+            }
+            comp => normalized.push(comp),
+        }
+    }
+
+    normalized
+}
+
+/// The path of `to` relative to the directory `from`, using `/` as the separator.
+///
+/// Both paths are expected to be normalized.
+pub fn relative_path(from: &Path, to: &Path) -> String {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from
+        .iter()
+        .zip(to.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut comps: Vec<String> = vec!["..".to_string(); from.len() - common];
+    comps.extend(
+        to[common..]
+            .iter()
+            .map(|comp| comp.as_os_str().to_string_lossy().into_owned()),
+    );
+    if comps.is_empty() {
+        return ".".to_string();
+    }
+
+    comps.join("/")
+}
+
+/// Converts an absolute path to a URI with a `file` scheme.
+pub fn path_to_uri(path: &Path) -> Option<Uri> {
+    let path = path.to_str()?.replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(b as char)
+            }
+            b => uri.push_str(&format!("%{b:02X}")),
+        }
+    }
+
+    Uri::from_str(&uri).ok()
+}
+
+/// Returns an edit that inserts an attribute after the last attribute of an element.
+///
+/// Accepts a node with a kind of "start_tag" or "self_closing_tag".
+pub fn insert_attr_edit(text: &Text, elem: Node, attr: &str) -> Option<TextEdit> {
+    let mut cursor = elem.walk();
+    let end = elem
+        .named_children(&mut cursor)
+        .filter(|n| matches!(n.kind(), "tag_name" | "attribute"))
+        .last()
+        .map_or(elem.start_position(), |n| n.end_position());
+
+    Some(TextEdit {
+        range: point_range(text, end, end)?,
+        new_text: String::from_iter([" ", attr]),
+    })
+}
+
+/// Returns an edit that removes an attribute along with the whitespace preceding it.
+///
+/// Accepts a node with a kind of "attribute".
+pub fn remove_attr_edit(text: &Text, attr: Node) -> Option<TextEdit> {
+    let start = attr
+        .prev_named_sibling()
+        .map_or(attr.start_position(), |n| n.end_position());
+
+    Some(TextEdit {
+        range: point_range(text, start, attr.end_position())?,
+        new_text: String::new(),
+    })
+}
+
+/// The Levenshtein distance between two strings.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let sub = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = sub.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{edit_distance, path_to_uri, relative_path, uri_to_path};
+
+    #[test]
+    fn edit_distance_between_strings() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("data-trunk", "data-trunk"), 0);
+        assert_eq!(edit_distance("data-trunks", "data-trunk"), 1);
+        assert_eq!(edit_distance("data_trunk", "data-trunk"), 1);
+        assert_eq!(edit_distance("wroker", "worker"), 2);
+        assert_eq!(edit_distance("", "css"), 3);
+    }
+
+    #[test]
+    fn path_uri_round_trip() {
+        let path = Path::new("/home/user/my site/index.html");
+        let uri = path_to_uri(path).unwrap();
+        assert_eq!(uri.as_str(), "file:///home/user/my%20site/index.html");
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+    }
+
+    #[test]
+    fn relative_paths() {
+        let rel = |from, to| relative_path(Path::new(from), Path::new(to));
+        assert_eq!(rel("/ws", "/ws/styles/main.scss"), "styles/main.scss");
+        assert_eq!(
+            rel("/ws/pages", "/ws/styles/main.scss"),
+            "../styles/main.scss"
+        );
+        assert_eq!(rel("/ws/pages", "/ws"), "..");
+        assert_eq!(rel("/ws", "/ws"), ".");
+    }
+}